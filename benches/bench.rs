@@ -1,10 +1,46 @@
 use std::time::Duration;
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use territory::world::World;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use libterritory::{
+    cells::Layout,
+    spawn::{Spawn, SpawnStrategy},
+    world::{Empire, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// A world that's been running for a while, so most cells are claimed.
+fn busy_world(size: usize) -> World {
+    spawned_world(size, SpawnStrategy::Territories, 20)
+}
+
+/// A world where a few empires just started out, so most of it is empty.
+fn sparse_world(size: usize) -> World {
+    spawned_world(size, SpawnStrategy::EvenlySpaced, 50)
+}
+
+fn spawned_world(size: usize, strategy: SpawnStrategy, ticks: usize) -> World {
+    let mut world = World::new(size, size);
+    for id in 1..=8 {
+        world.empires.push(Empire::new(
+            id,
+            format!("Empire {}", id),
+            (255, 255, 255, 255),
+        ));
+    }
+    world.seed = 0;
+    Spawn {
+        strategy,
+        fair: true,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(0));
+    for _ in 0..ticks {
+        world.update();
+    }
+    world
+}
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let mut group = c.benchmark_group("sample-size-example");
+    let mut group = c.benchmark_group("update");
     group.sample_size(20);
     group.measurement_time(Duration::from_secs_f32(20.0));
     group.bench_function("100 cycles 512x512", |b| {
@@ -18,6 +54,59 @@ fn criterion_benchmark(c: &mut Criterion) {
             black_box(world);
         })
     });
+    for size in [512, 2048] {
+        let mut world = busy_world(size);
+        group.bench_function(format!("1 cycle {0}x{0}", size), |b| {
+            b.iter(|| {
+                world.update();
+                black_box(&world);
+            })
+        });
+        group.bench_function(format!("1 cycle {0}x{0}, no tile skipping", size), |b| {
+            b.iter(|| {
+                world.update_full();
+                black_box(&world);
+            })
+        });
+        world.set_layout(Layout::StructOfArrays);
+        group.bench_function(format!("1 cycle {0}x{0} struct of arrays", size), |b| {
+            b.iter(|| {
+                world.update();
+                black_box(&world);
+            })
+        });
+    }
+    let world = sparse_world(2048);
+    group.bench_function("1 cycle 2048x2048 sparse", |b| {
+        b.iter_batched_ref(
+            || world.clone(),
+            |world| world.update(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("1 cycle 2048x2048 sparse, no tile skipping", |b| {
+        b.iter_batched_ref(
+            || world.clone(),
+            |world| world.update_full(),
+            BatchSize::LargeInput,
+        )
+    });
+    #[cfg(feature = "gpu")]
+    match libterritory::gpu::GpuBackend::new() {
+        Ok(mut gpu) => {
+            let mut world = busy_world(2048);
+            group.bench_function(
+                format!("1 cycle 2048x2048 on {}", gpu.adapter_name()),
+                |b| {
+                    b.iter(|| {
+                        gpu.update(&mut world);
+                        black_box(&world);
+                    })
+                },
+            );
+        }
+        Err(e) => eprintln!("skipping GPU benches: {}", e),
+    }
     group.finish();
 }
 
@@ -0,0 +1,20 @@
+/// Regenerates capi/territory.h, but only when `TERRITORY_C_HEADER` is set (as
+/// capi/Makefile does), so that building the crate never writes to its sources.
+fn main() {
+    println!("cargo:rerun-if-env-changed=TERRITORY_C_HEADER");
+    if std::env::var_os("TERRITORY_C_HEADER").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-changed=src/capi.rs");
+    println!("cargo:rerun-if-changed=src/world.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file("cbindgen.toml").expect("couldn't read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/capi.rs")
+        .with_src("src/world.rs")
+        .generate()
+        .expect("couldn't generate the C header")
+        .write_to_file("capi/territory.h");
+}
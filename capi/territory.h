@@ -0,0 +1,114 @@
+#ifndef TERRITORY_H
+#define TERRITORY_H
+
+/* Generated from src/capi.rs by build.rs, don't edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct TerritoryWorld TerritoryWorld;
+
+#if !defined(TERRITORY_WIDE_TROOPS)
+/**
+ * The troops in a cell, `u32` with the `wide-troops` feature.
+ */
+typedef uint16_t TerritoryTroops;
+#endif
+
+#if defined(TERRITORY_WIDE_TROOPS)
+typedef uint32_t TerritoryTroops;
+#endif
+
+typedef struct TerritoryCell {
+  uint16_t owner;
+  TerritoryTroops troops;
+} TerritoryCell;
+
+/**
+ * A new world with no empires. Free it with `territory_world_free`.
+ */
+struct TerritoryWorld *territory_world_new(size_t width, size_t height);
+
+/**
+ * A world built from the contents of a scenario file, or null if it isn't valid.
+ *
+ * # Safety
+ * `toml` must be null or a nul-terminated string.
+ */
+struct TerritoryWorld *territory_world_from_scenario(const char *toml);
+
+/**
+ * A copy of the whole world, which can be run on independently.
+ */
+struct TerritoryWorld *territory_world_snapshot(const struct TerritoryWorld *world);
+
+/**
+ * # Safety
+ * `world` must be null or come from this library, and not be used afterwards.
+ */
+void territory_world_free(struct TerritoryWorld *world);
+
+void territory_world_update(struct TerritoryWorld *world);
+
+size_t territory_world_width(const struct TerritoryWorld *world);
+
+size_t territory_world_height(const struct TerritoryWorld *world);
+
+size_t territory_world_tick(const struct TerritoryWorld *world);
+
+/**
+ * A hash of the tick, cells, terrain, garrisons and empires, the same for
+ * worlds that play out the same way. 0 for null.
+ */
+uint64_t territory_world_checksum(const struct TerritoryWorld *world);
+
+/**
+ * Writes the cell at `x`, `y` to `out`. False if it's out of bounds.
+ */
+bool territory_world_get_cell(const struct TerritoryWorld *world,
+                              size_t x,
+                              size_t y,
+                              struct TerritoryCell *out);
+
+/**
+ * False if the cell is out of bounds or its owner isn't an empire.
+ */
+bool territory_world_set_cell(struct TerritoryWorld *world,
+                              size_t x,
+                              size_t y,
+                              struct TerritoryCell cell);
+
+/**
+ * Returns the new empire's id, or 0 if `name` isn't valid UTF-8.
+ *
+ * # Safety
+ * `name` must be null or a nul-terminated string.
+ */
+uint16_t territory_world_add_empire(struct TerritoryWorld *world,
+                                    const char *name,
+                                    uint8_t r,
+                                    uint8_t g,
+                                    uint8_t b);
+
+/**
+ * Clears the world and places every empire, spread out evenly.
+ */
+void territory_world_spawn(struct TerritoryWorld *world, uint64_t seed, bool fair);
+
+/**
+ * The winning empire's id, or 0 while nobody has won.
+ */
+uint16_t territory_world_winner(const struct TerritoryWorld *world);
+
+/**
+ * Draws RGBA pixels into `frame`. False if `len` is less than `width * height * 4`.
+ *
+ * # Safety
+ * `frame` must be null or point to `len` writable bytes.
+ */
+bool territory_world_draw(const struct TerritoryWorld *world, uint8_t *frame, size_t len);
+
+#endif /* TERRITORY_H */
@@ -0,0 +1,118 @@
+/* Exercises the C API. Run with `make -C capi test`. */
+#include <assert.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "territory.h"
+
+#define WIDTH 64
+#define HEIGHT 48
+
+static const char *SCENARIO =
+    "width = 32\n"
+    "height = 32\n"
+    "seed = 1\n"
+    "[[empires]]\n"
+    "color = [255, 0, 0]\n"
+    "start = [4, 4]\n"
+    "troops = 30000\n"
+    "[[empires]]\n"
+    "color = [0, 0, 255]\n"
+    "start = [28, 28]\n"
+    "troops = 30000\n";
+
+static void test_cells(void) {
+  TerritoryWorld *world = territory_world_new(WIDTH, HEIGHT);
+  assert(territory_world_width(world) == WIDTH);
+  assert(territory_world_height(world) == HEIGHT);
+
+  /* Owners have to exist, and cells have to be in bounds. */
+  TerritoryCell cell = {.owner = 1, .troops = 1000};
+  assert(!territory_world_set_cell(world, 3, 4, cell));
+  assert(territory_world_add_empire(world, "Red", 255, 0, 0) == 1);
+  assert(territory_world_set_cell(world, 3, 4, cell));
+  assert(!territory_world_set_cell(world, WIDTH, 0, cell));
+
+  TerritoryCell out;
+  assert(territory_world_get_cell(world, 3, 4, &out));
+  assert(out.owner == 1 && out.troops == 1000);
+  assert(!territory_world_get_cell(world, 0, HEIGHT, &out));
+
+  territory_world_free(world);
+}
+
+static void test_update_and_snapshot(void) {
+  TerritoryWorld *world = territory_world_new(WIDTH, HEIGHT);
+  territory_world_add_empire(world, "Red", 255, 0, 0);
+  territory_world_add_empire(world, "Blue", 0, 0, 255);
+  territory_world_spawn(world, 7, true);
+
+  TerritoryWorld *snapshot = territory_world_snapshot(world);
+  for (int i = 0; i < 20; i++) {
+    territory_world_update(world);
+    territory_world_update(snapshot);
+  }
+  assert(territory_world_tick(world) == 20);
+
+  /* Same seed, same state, so the copy plays out the same. */
+  size_t claimed = 0;
+  for (size_t y = 0; y < HEIGHT; y++) {
+    for (size_t x = 0; x < WIDTH; x++) {
+      TerritoryCell a, b;
+      territory_world_get_cell(world, x, y, &a);
+      territory_world_get_cell(snapshot, x, y, &b);
+      assert(a.owner == b.owner && a.troops == b.troops);
+      claimed += a.owner != 0;
+    }
+  }
+  assert(claimed > 2);
+  assert(territory_world_checksum(world) == territory_world_checksum(snapshot));
+  territory_world_update(snapshot);
+  assert(territory_world_checksum(world) != territory_world_checksum(snapshot));
+  assert(territory_world_checksum(NULL) == 0);
+
+  size_t len = WIDTH * HEIGHT * 4;
+  uint8_t *frame = malloc(len);
+  assert(!territory_world_draw(world, frame, len - 1));
+  assert(territory_world_draw(world, frame, len));
+  free(frame);
+
+  territory_world_free(snapshot);
+  territory_world_free(world);
+}
+
+static void test_scenario(void) {
+  TerritoryWorld *world = territory_world_from_scenario(SCENARIO);
+  assert(world != NULL);
+  assert(territory_world_width(world) == 32);
+
+  TerritoryCell out;
+  territory_world_get_cell(world, 28, 28, &out);
+  assert(out.owner == 2);
+
+  for (int i = 0; i < 5000 && territory_world_winner(world) == 0; i++) {
+    territory_world_update(world);
+  }
+  assert(territory_world_winner(world) != 0);
+  territory_world_free(world);
+
+  assert(territory_world_from_scenario("width = \"wide\"") == NULL);
+}
+
+static void test_null(void) {
+  territory_world_update(NULL);
+  territory_world_free(NULL);
+  assert(territory_world_width(NULL) == 0);
+  assert(territory_world_snapshot(NULL) == NULL);
+  assert(territory_world_from_scenario(NULL) == NULL);
+}
+
+int main(void) {
+  test_cells();
+  test_update_and_snapshot();
+  test_scenario();
+  test_null();
+  printf("all C API tests passed\n");
+  return 0;
+}
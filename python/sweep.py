@@ -0,0 +1,31 @@
+"""Sweeps decay rates and reports how long duels take and who wins.
+
+    maturin develop --release
+    python python/sweep.py
+"""
+import territory
+
+SIZE = 128
+RUNS = 8
+MAX_TICKS = 20_000
+
+
+def duel(decay, seed):
+    world = territory.World(SIZE, SIZE, seed=seed)
+    world.decay = decay
+    world.add_empire("Red", (255, 40, 40))
+    world.add_empire("Blue", (40, 80, 255))
+    world.spawn("evenly_spaced", fair=True, seed=seed)
+    winner = world.run(MAX_TICKS)
+    return winner, world.tick, (world.owners != 0).mean()
+
+
+for decay in [0.90, 0.93, 0.95, 0.97, 0.99]:
+    results = [duel(decay, seed) for seed in range(RUNS)]
+    finished = [ticks for winner, ticks, _ in results if winner is not None]
+    claimed = sum(share for _, _, share in results) / RUNS
+    print(
+        f"decay {decay:.2f}: {len(finished)}/{RUNS} finished, "
+        f"{sum(finished) / max(len(finished), 1):.0f} ticks on average, "
+        f"{claimed:.0%} claimed at the end"
+    )
@@ -0,0 +1,42 @@
+//! Runs a sweep file headlessly and prints the summary.
+//!
+//! `sweep <file.toml> [--curves <out.csv>]`
+use std::{fs::File, io::BufWriter, process::exit, time::Instant};
+
+use libterritory::sweep::Sweep;
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (path, curves) = match args.as_slice() {
+        [path] => (path, None),
+        [path, flag, curves] if flag == "--curves" => (path, Some(curves)),
+        _ => {
+            eprintln!("usage: sweep <file.toml> [--curves <out.csv>]");
+            exit(2);
+        }
+    };
+
+    let sweep = Sweep::load(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        exit(1);
+    });
+    let matches = sweep.matches().len();
+    eprintln!("playing {} matches", matches);
+
+    let start = Instant::now();
+    let report = sweep.run().unwrap_or_else(|e| {
+        eprintln!("{}: {}", path, e);
+        exit(1);
+    });
+    eprintln!("done in {:.1?}", start.elapsed());
+    print!("{}", report);
+
+    if let Some(curves) = curves {
+        let written =
+            File::create(curves).and_then(|file| report.write_curves(BufWriter::new(file)));
+        if let Err(e) = written {
+            eprintln!("{}: {}", curves, e);
+            exit(1);
+        }
+    }
+}
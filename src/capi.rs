@@ -0,0 +1,192 @@
+//! C API for the `cdylib`. The header is generated into `capi/territory.h` by
+//! the build script when `TERRITORY_C_HEADER` is set, which `make -C capi`
+//! does, and `capi/test.c` shows how to use it.
+//!
+//! Worlds are passed around as opaque pointers. Every function accepts null
+//! and does nothing (or returns false/0/null) in that case.
+//!
+//! With the `wide-troops` feature, C code has to be compiled with
+//! `TERRITORY_WIDE_TROOPS` defined to match.
+use std::{
+    ffi::{c_char, CStr},
+    slice,
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    world::{Cell, Empire, World},
+};
+
+/// A new world with no empires. Free it with `territory_world_free`.
+#[no_mangle]
+pub extern "C" fn territory_world_new(width: usize, height: usize) -> *mut World {
+    Box::into_raw(Box::new(World::new(width, height)))
+}
+
+/// A world built from the contents of a scenario file, or null if it isn't valid.
+///
+/// # Safety
+/// `toml` must be null or a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn territory_world_from_scenario(toml: *const c_char) -> *mut World {
+    let Some(toml) = str_arg(toml) else {
+        return std::ptr::null_mut();
+    };
+    match Scenario::from_toml(toml).and_then(|scenario| scenario.build()) {
+        Ok(world) => Box::into_raw(Box::new(world)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// A copy of the whole world, which can be run on independently.
+#[no_mangle]
+pub extern "C" fn territory_world_snapshot(world: Option<&World>) -> *mut World {
+    match world {
+        Some(world) => Box::into_raw(Box::new(world.clone())),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `world` must be null or come from this library, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn territory_world_free(world: *mut World) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn territory_world_update(world: Option<&mut World>) {
+    if let Some(world) = world {
+        world.update();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn territory_world_width(world: Option<&World>) -> usize {
+    world.map_or(0, |world| world.width)
+}
+
+#[no_mangle]
+pub extern "C" fn territory_world_height(world: Option<&World>) -> usize {
+    world.map_or(0, |world| world.height)
+}
+
+#[no_mangle]
+pub extern "C" fn territory_world_tick(world: Option<&World>) -> usize {
+    world.map_or(0, |world| world.tick)
+}
+
+/// A hash of the tick, cells, terrain, garrisons and empires, the same for
+/// worlds that play out the same way. 0 for null.
+#[no_mangle]
+pub extern "C" fn territory_world_checksum(world: Option<&World>) -> u64 {
+    world.map_or(0, World::checksum)
+}
+
+/// Writes the cell at `x`, `y` to `out`. False if it's out of bounds.
+#[no_mangle]
+pub extern "C" fn territory_world_get_cell(
+    world: Option<&World>,
+    x: usize,
+    y: usize,
+    out: Option<&mut Cell>,
+) -> bool {
+    match (world, out) {
+        (Some(world), Some(out)) if x < world.width && y < world.height => {
+            *out = world.cells.get(y * world.width + x);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// False if the cell is out of bounds or its owner isn't an empire.
+#[no_mangle]
+pub extern "C" fn territory_world_set_cell(
+    world: Option<&mut World>,
+    x: usize,
+    y: usize,
+    cell: Cell,
+) -> bool {
+    match world {
+        Some(world)
+            if x < world.width
+                && y < world.height
+                && cell.owner as usize <= world.empires.len() =>
+        {
+            world.set(x as isize, y as isize, cell);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Returns the new empire's id, or 0 if `name` isn't valid UTF-8.
+///
+/// # Safety
+/// `name` must be null or a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn territory_world_add_empire(
+    world: Option<&mut World>,
+    name: *const c_char,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> u16 {
+    let (Some(world), Some(name)) = (world, str_arg(name)) else {
+        return 0;
+    };
+    let id = (world.empires.len() + 1) as u16;
+    world.empires.push(Empire::new(id, name, (r, g, b, 255)));
+    id
+}
+
+/// Clears the world and places every empire, spread out evenly.
+#[no_mangle]
+pub extern "C" fn territory_world_spawn(world: Option<&mut World>, seed: u64, fair: bool) {
+    if let Some(world) = world {
+        Spawn {
+            strategy: SpawnStrategy::EvenlySpaced,
+            fair,
+        }
+        .apply(world, &mut StdRng::seed_from_u64(seed));
+    }
+}
+
+/// The winning empire's id, or 0 while nobody has won.
+#[no_mangle]
+pub extern "C" fn territory_world_winner(world: Option<&World>) -> u16 {
+    world.and_then(World::winner).unwrap_or(0)
+}
+
+/// Draws RGBA pixels into `frame`. False if `len` is less than `width * height * 4`.
+///
+/// # Safety
+/// `frame` must be null or point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn territory_world_draw(
+    world: Option<&World>,
+    frame: *mut u8,
+    len: usize,
+) -> bool {
+    match world {
+        Some(world) if !frame.is_null() && len >= world.cells.len() * 4 => {
+            world.draw(slice::from_raw_parts_mut(frame, world.cells.len() * 4));
+            true
+        }
+        _ => false,
+    }
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
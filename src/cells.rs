@@ -0,0 +1,238 @@
+//! Storage for a world's cells, either as `(owner, troops)` pairs or as separate
+//! owner and troop planes. Everything outside of `World::update` goes through
+//! [`Cells::get`], [`Cells::set`] and [`Cells::iter`], so it works with both.
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::{IndexedParallelIterator, ParallelIterator, ParallelSliceMut};
+use serde::{Deserialize, Serialize};
+
+use crate::world::{Cell, Troops};
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// one `Vec<Cell>`
+    #[default]
+    ArrayOfStructs,
+    /// A plane of owners and a plane of troops, which lets the update work on
+    /// runs of troops at once.
+    StructOfArrays,
+}
+impl Layout {
+    pub const ALL: [Layout; 2] = [Layout::ArrayOfStructs, Layout::StructOfArrays];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::ArrayOfStructs => "Array of structs",
+            Layout::StructOfArrays => "Struct of arrays",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Cells {
+    pub(crate) storage: Storage,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Storage {
+    Aos(Vec<Cell>),
+    Soa {
+        owners: Vec<u16>,
+        troops: Vec<Troops>,
+    },
+}
+
+impl Cells {
+    pub fn new(layout: Layout, len: usize) -> Self {
+        Self {
+            storage: match layout {
+                Layout::ArrayOfStructs => Storage::Aos(vec![Cell::default(); len]),
+                Layout::StructOfArrays => Storage::Soa {
+                    owners: vec![0; len],
+                    troops: vec![0; len],
+                },
+            },
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        match self.storage {
+            Storage::Aos(_) => Layout::ArrayOfStructs,
+            Storage::Soa { .. } => Layout::StructOfArrays,
+        }
+    }
+
+    /// The same cells, stored as `layout`.
+    pub fn to_layout(&self, layout: Layout) -> Self {
+        let mut cells = Self::new(layout, self.len());
+        for (i, cell) in self.iter().enumerate() {
+            cells.set(i, cell);
+        }
+        cells
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Aos(cells) => cells.len(),
+            Storage::Soa { owners, .. } => owners.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> Cell {
+        match &self.storage {
+            Storage::Aos(cells) => cells[i],
+            Storage::Soa { owners, troops } => Cell {
+                owner: owners[i],
+                troops: troops[i],
+            },
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, i: usize, cell: Cell) {
+        match &mut self.storage {
+            Storage::Aos(cells) => cells[i] = cell,
+            Storage::Soa { owners, troops } => {
+                owners[i] = cell.owner;
+                troops[i] = cell.troops;
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Cell> + '_ {
+        (0..self.len()).map(|i| self.get(i))
+    }
+
+    /// Overwrites these cells with `other`'s, keeping them where they are
+    /// stored. Both must be as long and stored the same way.
+    #[cfg(feature = "python")]
+    pub(crate) fn copy_from(&mut self, other: &Cells) {
+        match (&mut self.storage, &other.storage) {
+            (Storage::Aos(cells), Storage::Aos(other)) => cells.copy_from_slice(other),
+            (
+                Storage::Soa { owners, troops },
+                Storage::Soa {
+                    owners: other_owners,
+                    troops: other_troops,
+                },
+            ) => {
+                owners.copy_from_slice(other_owners);
+                troops.copy_from_slice(other_troops);
+            }
+            _ => unreachable!("layouts don't match"),
+        }
+    }
+
+    pub fn fill(&mut self, cell: Cell) {
+        match &mut self.storage {
+            Storage::Aos(cells) => cells.fill(cell),
+            Storage::Soa { owners, troops } => {
+                owners.fill(cell.owner);
+                troops.fill(cell.troops);
+            }
+        }
+    }
+
+    /// Calls `f` with every `band_len` long chunk, in parallel if `parallel`
+    /// and where possible.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub(crate) fn for_each_band(
+        &mut self,
+        band_len: usize,
+        parallel: bool,
+        f: impl Fn(usize, CellsMut) + Send + Sync,
+    ) {
+        match &mut self.storage {
+            Storage::Aos(cells) => {
+                let f = |(i, band)| f(i, CellsMut::Aos(band));
+                #[cfg(not(target_arch = "wasm32"))]
+                if parallel {
+                    cells.par_chunks_mut(band_len).enumerate().for_each(f);
+                    return;
+                }
+                cells.chunks_mut(band_len).enumerate().for_each(f);
+            }
+            Storage::Soa { owners, troops } => {
+                let f = |(i, (owners, troops))| f(i, CellsMut::Soa { owners, troops });
+                #[cfg(not(target_arch = "wasm32"))]
+                if parallel {
+                    owners
+                        .par_chunks_mut(band_len)
+                        .zip(troops.par_chunks_mut(band_len))
+                        .enumerate()
+                        .for_each(f);
+                    return;
+                }
+                owners
+                    .chunks_mut(band_len)
+                    .zip(troops.chunks_mut(band_len))
+                    .enumerate()
+                    .for_each(f);
+            }
+        }
+    }
+}
+
+/// Compares the cells themselves, not how they're stored.
+impl PartialEq for Cells {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl Eq for Cells {}
+
+/// A mutable window into `Cells`.
+pub(crate) enum CellsMut<'a> {
+    Aos(&'a mut [Cell]),
+    Soa {
+        owners: &'a mut [u16],
+        troops: &'a mut [Troops],
+    },
+}
+impl<'a> CellsMut<'a> {
+    pub(crate) fn slice(&mut self, start: usize, end: usize) -> CellsMut<'_> {
+        match self {
+            CellsMut::Aos(cells) => CellsMut::Aos(&mut cells[start..end]),
+            CellsMut::Soa { owners, troops } => CellsMut::Soa {
+                owners: &mut owners[start..end],
+                troops: &mut troops[start..end],
+            },
+        }
+    }
+
+    #[inline]
+    pub(crate) fn set(&mut self, i: usize, cell: Cell) {
+        match self {
+            CellsMut::Aos(cells) => cells[i] = cell,
+            CellsMut::Soa { owners, troops } => {
+                owners[i] = cell.owner;
+                troops[i] = cell.troops;
+            }
+        }
+    }
+
+    /// Copies the matching run of `from`, starting at `start`. Layouts must match.
+    pub(crate) fn copy_from(&mut self, from: &Cells, start: usize) {
+        match (self, &from.storage) {
+            (CellsMut::Aos(cells), Storage::Aos(from)) => {
+                cells.copy_from_slice(&from[start..start + cells.len()])
+            }
+            (
+                CellsMut::Soa { owners, troops },
+                Storage::Soa {
+                    owners: from_owners,
+                    troops: from_troops,
+                },
+            ) => {
+                owners.copy_from_slice(&from_owners[start..start + owners.len()]);
+                troops.copy_from_slice(&from_troops[start..start + troops.len()]);
+            }
+            _ => unreachable!("layouts don't match"),
+        }
+    }
+}
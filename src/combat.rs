@@ -0,0 +1,107 @@
+//! How battles between cells of different empires play out, set per world with
+//! `SimParams::combat`.
+//!
+//! A cell is still attacked by the first neighbor stronger than it, in a random
+//! order. With [`Combat::Takeover`] that neighbor simply takes it over with a
+//! copy of its troops, so fighting never costs anything. The other models make
+//! both sides pay: the cell it attacked from sends all of its troops, split
+//! evenly between every cell it attacks that tick, and whichever side is left
+//! standing holds each attacked cell with what's left.
+//!
+//! ```toml
+//! [params.combat]
+//! type = "lanchester"
+//! exponent = 2.0
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::rng::CellRng;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Combat {
+    /// the attacker takes the cell over with a copy of its troops
+    #[default]
+    Takeover,
+    /// Lanchester's laws: the stronger side wins with `(a^exponent -
+    /// b^exponent)^(1/exponent)` left. 1 is the linear law, where the winner
+    /// keeps the difference, and 2 the square law, where bigger armies lose
+    /// less of themselves.
+    Lanchester { exponent: f32 },
+    /// Both sides roll a die with `sides` sides and fight at their troops times
+    /// their roll, so the weaker side can win. The winner keeps the difference.
+    Dice { sides: u32 },
+}
+
+/// What's left of each side after a battle, in troops.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    pub attacker: f32,
+    pub defender: f32,
+}
+
+impl Combat {
+    pub fn name(self) -> &'static str {
+        match self {
+            Combat::Takeover => "takeover",
+            Combat::Lanchester { .. } => "lanchester",
+            Combat::Dice { .. } => "dice",
+        }
+    }
+
+    /// Whether battles cost the attacker anything.
+    pub fn has_losses(self) -> bool {
+        self != Combat::Takeover
+    }
+
+    /// Fights `attacker` troops against `defender` ones, which count `defense`
+    /// times over. The defender holds on ties.
+    pub(crate) fn fight(
+        self,
+        attacker: f32,
+        defender: f32,
+        defense: f32,
+        rng: &mut CellRng,
+    ) -> Outcome {
+        // Each side's strength per troop, and the exponent of the law.
+        let (a_each, d_each, exponent) = match self {
+            Combat::Takeover => {
+                return Outcome {
+                    attacker,
+                    defender: 0.0,
+                }
+            }
+            Combat::Lanchester { exponent } => (1.0, defense, exponent.max(f32::EPSILON)),
+            Combat::Dice { sides } => {
+                let mut roll = || (rng.below(sides.max(1)) + 1) as f32;
+                (roll(), roll() * defense, 1.0)
+            }
+        };
+
+        let (a, d) = (attacker * a_each, defender * d_each);
+        let left = remainder(a.max(d), a.min(d), exponent);
+        if a > d {
+            Outcome {
+                attacker: left / a_each,
+                defender: 0.0,
+            }
+        } else {
+            Outcome {
+                attacker: 0.0,
+                defender: left / d_each.max(f32::EPSILON),
+            }
+        }
+    }
+}
+
+/// `(a^exponent - b^exponent)^(1/exponent)`. The usual laws don't go through
+/// `powf`, which isn't rounded the same way everywhere.
+fn remainder(a: f32, b: f32, exponent: f32) -> f32 {
+    if exponent == 1.0 {
+        a - b
+    } else if exponent == 2.0 {
+        (a * a - b * b).sqrt()
+    } else {
+        (a.powf(exponent) - b.powf(exponent)).powf(exponent.recip())
+    }
+}
@@ -0,0 +1,147 @@
+//! Seasons and other events that change the rules for every empire at once,
+//! on a schedule in `params.events`. An event is on for `duration` ticks from
+//! tick `start`, and again every `every` ticks after that if it isn't 0.
+//!
+//! ```toml
+//! [[params.events]]
+//! start = 750
+//! every = 1000
+//! duration = 250
+//! event = { type = "winter", decay = 3.0 }
+//!
+//! [[params.events]]
+//! start = 2000
+//! duration = 100
+//! event = { type = "plague", density = 20000, loss = 0.05 }
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    traits::Traits,
+    world::{Cell, Troops, World},
+};
+
+/// Something that happens to the whole world for a while.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Event {
+    /// cells lose `decay` times as many troops to decay
+    Winter { decay: f32 },
+    /// takeovers and reinforcements bring `growth` times as many troops
+    Harvest { growth: f32 },
+    /// owned cells whose neighborhood holds at least `density` troops a cell
+    /// on average lose `loss` of their troops each tick
+    Plague { density: Troops, loss: f32 },
+}
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Winter { .. } => "Winter",
+            Event::Harvest { .. } => "Harvest",
+            Event::Plague { .. } => "Plague",
+        }
+    }
+
+    /// What it does to every empire's traits.
+    fn traits(self) -> Traits {
+        match self {
+            Event::Winter { decay } => Traits {
+                decay,
+                ..Default::default()
+            },
+            Event::Harvest { growth } => Traits {
+                growth,
+                ..Default::default()
+            },
+            Event::Plague { .. } => Traits::default(),
+        }
+    }
+}
+
+/// An event and when it's on, part of `SimParams`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scheduled {
+    pub event: Event,
+    #[serde(default)]
+    pub start: usize,
+    /// ticks from one start to the next, 0 for only once
+    #[serde(default)]
+    pub every: usize,
+    pub duration: usize,
+}
+impl Scheduled {
+    pub fn active(&self, tick: usize) -> bool {
+        let Some(since) = tick.checked_sub(self.start) else {
+            return false;
+        };
+        let since = if self.every > 0 {
+            since % self.every
+        } else {
+            since
+        };
+        since < self.duration
+    }
+}
+
+impl World {
+    /// The events that are on this tick, in the order of `params.events`.
+    pub fn active_events(&self) -> impl Iterator<Item = Event> + '_ {
+        self.params
+            .events
+            .iter()
+            .filter(|scheduled| scheduled.active(self.tick))
+            .map(|scheduled| scheduled.event)
+    }
+
+    /// What the events on this tick do to every empire's traits.
+    pub(crate) fn seasonal_traits(&self) -> Traits {
+        self.active_events()
+            .fold(Traits::default(), |traits, event| {
+                traits.stack(&event.traits())
+            })
+    }
+
+    /// Thins out the troops in dense regions while a plague is on.
+    pub(crate) fn plague(&mut self) {
+        let plagues = self
+            .active_events()
+            .filter_map(|event| match event {
+                Event::Plague { density, loss } => Some((density, loss.clamp(0.0, 1.0))),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if plagues.is_empty() {
+            return;
+        }
+
+        // Densities from before anyone got sick, so that the order of the cells
+        // doesn't matter.
+        let densities = (0..self.cells.len())
+            .map(|i| {
+                let (cells, troops) = self
+                    .neighbor_indices(i)
+                    .into_iter()
+                    .flatten()
+                    .chain([i])
+                    .fold((0, 0), |(cells, troops), j| {
+                        (cells + 1, troops + self.cells.get(j).troops as u64)
+                    });
+                troops / cells
+            })
+            .collect::<Vec<_>>();
+        for (i, density) in densities.into_iter().enumerate() {
+            let cell = self.cells.get(i);
+            if cell.owner == 0 {
+                continue;
+            }
+            let mut troops = cell.troops;
+            for &(threshold, loss) in &plagues {
+                if density >= threshold as u64 {
+                    troops = (troops as f32 * (1.0 - loss)) as Troops;
+                }
+            }
+            self.cells.set(i, Cell { troops, ..cell });
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Troops moving through an empire's own territory toward its borders, so the
+//! interior reinforces the frontier instead of sitting idle.
+use std::collections::VecDeque;
+
+use crate::world::{Cell, Troops, World};
+
+/// The distance of unowned cells, and of cells that can't reach a border.
+pub const UNREACHABLE: u32 = u32::MAX;
+
+impl World {
+    /// How many steps each owned cell is from its empire's border, going
+    /// through its own territory. Border cells, next to a claimable cell that
+    /// isn't theirs, are 0. Coastlines and the edges of a bounded world aren't
+    /// borders.
+    pub fn frontier_distances(&self) -> Vec<u32> {
+        let mut distances = vec![UNREACHABLE; self.cells.len()];
+        let mut queue = VecDeque::new();
+        for (i, distance) in distances.iter_mut().enumerate() {
+            let owner = self.cells.get(i).owner;
+            let border = self
+                .neighbor_indices(i)
+                .into_iter()
+                .flatten()
+                .any(|j| self.cells.get(j).owner != owner && self.terrain[j].claimable());
+            if owner != 0 && border {
+                *distance = 0;
+                queue.push_back(i);
+            }
+        }
+
+        while let Some(i) = queue.pop_front() {
+            let owner = self.cells.get(i).owner;
+            for j in self.neighbor_indices(i).into_iter().flatten() {
+                if distances[j] == UNREACHABLE && self.cells.get(j).owner == owner {
+                    distances[j] = distances[i] + 1;
+                    queue.push_back(j);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Moves `params.flow` of each interior cell's troops to its neighbors that
+    /// are closer to the border, split evenly between them. Shares round down
+    /// and come out of the sender, so every empire keeps the same troops, apart
+    /// from what won't fit under `params.max_troops`.
+    pub(crate) fn flow(&mut self) {
+        let rate = self.params.flow.min(1.0);
+        if rate <= 0.0 {
+            return;
+        }
+        let distances = self.frontier_distances();
+
+        let mut troops = self
+            .cells
+            .iter()
+            .map(|cell| cell.troops as u64)
+            .collect::<Vec<_>>();
+        for (i, &distance) in distances.iter().enumerate() {
+            // Interior cells only have neighbors of their own empire.
+            if distance == 0 || distance == UNREACHABLE {
+                continue;
+            }
+            let closer = self
+                .neighbor_indices(i)
+                .into_iter()
+                .flatten()
+                .filter(|&j| distances[j] < distance)
+                .collect::<Vec<_>>();
+            if closer.is_empty() {
+                continue;
+            }
+            let share = (self.cells.get(i).troops as f32 * rate / closer.len() as f32) as u64;
+            for j in closer {
+                troops[i] -= share;
+                troops[j] += share;
+            }
+        }
+
+        let max = self.params.max_troops as u64;
+        for (i, troops) in troops.into_iter().enumerate() {
+            let cell = self.cells.get(i);
+            self.cells.set(
+                i,
+                Cell {
+                    troops: troops.min(max) as Troops,
+                    ..cell
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Fog of war. With `params.vision` set, each empire only sees the cells within
+//! that many cells of its territory, in a square around each of its cells.
+//! What each empire sees is kept in `World::visibility` and updated every tick.
+//!
+//! Anything that plays an empire, like a player over the network or a bot,
+//! should only look at the world through [`World::observe`] or
+//! [`World::visible_cells`], and draw it with [`World::draw_for`].
+//!
+//! ```toml
+//! [params]
+//! vision = 8
+//! ```
+use crate::world::{Cell, Topology, World};
+
+/// The cells one empire can see, a bit each.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Visibility {
+    bits: Vec<u64>,
+}
+impl Visibility {
+    fn new(len: usize) -> Self {
+        Self {
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        self.bits
+            .get(i / 64)
+            .is_some_and(|word| word & (1 << (i % 64)) != 0)
+    }
+
+    fn set(&mut self, i: usize) {
+        self.bits[i / 64] |= 1 << (i % 64);
+    }
+
+    /// How many cells are visible.
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+}
+
+impl World {
+    /// Works out what each empire sees of the world as it is now. `update`
+    /// does this every tick, this is for after changing the world from
+    /// outside.
+    pub fn update_visibility(&mut self) {
+        let radius = self.params.vision;
+        if radius == 0 || self.cells.is_empty() {
+            self.visibility.clear();
+            return;
+        }
+        let (w, h) = (self.width, self.height);
+        let wrap = self.topology == Topology::Torus;
+
+        let mut owners = vec![vec![]; self.empires.len()];
+        for (i, cell) in self.cells.iter().enumerate() {
+            if let Some(cells) = owners.get_mut((cell.owner as usize).wrapping_sub(1)) {
+                cells.push(i);
+            }
+        }
+
+        let mut rows = vec![false; w * h];
+        let (mut line, mut spread_line) = (vec![false; h], vec![false; h]);
+        self.visibility = owners
+            .into_iter()
+            .map(|cells| {
+                let mut visibility = Visibility::new(w * h);
+                if cells.is_empty() {
+                    return visibility;
+                }
+                // Spread along the rows, then along the columns of that.
+                let mut owned = vec![false; w * h];
+                for i in cells {
+                    owned[i] = true;
+                }
+                for (row, out) in owned.chunks_exact(w).zip(rows.chunks_exact_mut(w)) {
+                    spread(row, out, radius, wrap);
+                }
+                for x in 0..w {
+                    for y in 0..h {
+                        line[y] = rows[y * w + x];
+                    }
+                    spread(&line, &mut spread_line, radius, wrap);
+                    for (y, &seen) in spread_line.iter().enumerate() {
+                        if seen {
+                            visibility.set(y * w + x);
+                        }
+                    }
+                }
+                visibility
+            })
+            .collect();
+    }
+
+    /// Whether `empire` can see cell `i`. Everyone sees everything without fog.
+    pub fn visible(&self, empire: u16, i: usize) -> bool {
+        if self.params.vision == 0 {
+            return true;
+        }
+        self.visibility
+            .get((empire as usize).wrapping_sub(1))
+            .is_some_and(|visibility| visibility.get(i))
+    }
+
+    /// The cells `empire` can see, with their indices.
+    pub fn visible_cells(&self, empire: u16) -> impl Iterator<Item = (usize, Cell)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(move |&(i, _)| self.visible(empire, i))
+    }
+
+    /// Every cell the way `empire` sees it, `None` for those it can't see.
+    pub fn observe(&self, empire: u16) -> Vec<Option<Cell>> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| self.visible(empire, i).then_some(cell))
+            .collect()
+    }
+
+    /// Like [`World::draw`], but the way `empire` sees it: only the terrain of
+    /// the cells it can't see, darkened.
+    pub fn draw_for(&self, empire: u16, frame: &mut [u8]) {
+        self.draw(frame);
+        if self.params.vision == 0 {
+            return;
+        }
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            if !self.visible(empire, i) {
+                let [r, g, b, a] = self.terrain[i].color();
+                pixel.copy_from_slice(&[r / 3, g / 3, b / 3, a]);
+            }
+        }
+    }
+}
+
+/// Sets each of `out` to whether any of `line` within `radius` of it is set,
+/// wrapping around the ends if `wrap`.
+fn spread(line: &[bool], out: &mut [bool], radius: usize, wrap: bool) {
+    let n = line.len();
+    if wrap && 2 * radius + 1 >= n {
+        out.fill(line.contains(&true));
+        return;
+    }
+    // How many are set before each index.
+    let mut before = Vec::with_capacity(n + 1);
+    before.push(0);
+    for &set in line {
+        before.push(before.last().unwrap() + set as usize);
+    }
+    let count = |lo: usize, hi: usize| before[hi + 1] - before[lo];
+
+    for (k, out) in out.iter_mut().enumerate() {
+        let (lo, hi) = (k as isize - radius as isize, k + radius);
+        let set = if !wrap {
+            count(lo.max(0) as usize, hi.min(n - 1))
+        } else if lo < 0 {
+            count(0, hi) + count((n as isize + lo) as usize, n - 1)
+        } else if hi >= n {
+            count(lo as usize, n - 1) + count(0, hi - n)
+        } else {
+            count(lo as usize, hi)
+        };
+        *out = set > 0;
+    }
+}
@@ -0,0 +1,341 @@
+//! Runs `World::update` as a wgpu compute shader, with the cells in a storage
+//! buffer. The shader uses the same per-cell randomness as the CPU, so both
+//! produce the same world, up to the odd float rounding difference.
+//!
+//! Works on software adapters like lavapipe or llvmpipe too, just slowly.
+use std::{error::Error, fmt, sync::mpsc};
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    terrain::Terrain,
+    traits::Traits,
+    world::{Cell, Topology, Troops, World},
+};
+
+const WORKGROUP: u32 = 8;
+
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter: String,
+    buffers: Option<Buffers>,
+}
+
+/// Everything that depends on the world's size.
+struct Buffers {
+    len: usize,
+    params: wgpu::Buffer,
+    cells_in: wgpu::Buffer,
+    cells_out: wgpu::Buffer,
+    defense: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// what's currently in `defense`
+    terrain: Vec<Terrain>,
+}
+
+impl GpuBackend {
+    /// Picks the first adapter that can run compute shaders, falling back to a
+    /// software one if there's no hardware adapter.
+    pub fn new() -> Result<Self, GpuError> {
+        pollster::block_on(Self::new_async())
+    }
+
+    async fn new_async() -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    force_fallback_adapter,
+                    compatible_surface: None,
+                })
+                .await
+                .filter(|adapter| {
+                    adapter
+                        .get_downlevel_capabilities()
+                        .flags
+                        .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+                });
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(GpuError::NoAdapter)?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("territory"),
+                    features: wgpu::Features::empty(),
+                    limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+                },
+                None,
+            )
+            .await
+            .map_err(GpuError::RequestDevice)?;
+
+        let module = device.create_shader_module(wgpu::include_wgsl!("shaders/update.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("update"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            adapter: adapter.get_info().name,
+            buffers: None,
+        })
+    }
+
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter
+    }
+
+    /// Whether the shader implements everything `world` uses. Cells are packed
+    /// into 32 bits, so wide troops aren't. Neither is anything that looks further
+    /// than a cell's neighbors: battles with losses, troop flow, attrition and
+    /// rebellions, nor troops in unclaimed cells. Empires all play by the same
+    /// rules there, so traits, research and events aren't either.
+    pub fn supports(&self, world: &World) -> bool {
+        let limits = self.device.limits();
+        let size = (world.cells.len() * 4) as u64;
+        !cfg!(feature = "wide-troops")
+            && !world.cells.is_empty()
+            && !world.scripted()
+            && !world.params.combat.has_losses()
+            && world.params.flow <= 0.0
+            && world.params.attrition <= 0.0
+            && world.params.rebellion.interval == 0
+            && world.params.tech.per_cell == 0
+            && world.params.events.is_empty()
+            && world
+                .empires
+                .iter()
+                .all(|empire| world.empire_traits(empire) == Traits::NEUTRAL)
+            && world.garrisons.iter().all(|&garrison| garrison == 0)
+            && world
+                .cells
+                .iter()
+                .all(|cell| cell.owner != 0 || cell.troops == 0)
+            && size <= limits.max_storage_buffer_binding_size as u64
+            && (world.width as u32).div_ceil(WORKGROUP)
+                <= limits.max_compute_workgroups_per_dimension
+            && (world.height as u32).div_ceil(WORKGROUP)
+                <= limits.max_compute_workgroups_per_dimension
+    }
+
+    /// Same as `world.update()`, but on the GPU. Falls back to the CPU if the
+    /// world isn't [supported](Self::supports) or the GPU fails.
+    pub fn update(&mut self, world: &mut World) {
+        if !self.supports(world) {
+            world.update();
+            return;
+        }
+        match self.step(world) {
+            Ok(()) => world.update_visibility(),
+            Err(err) => {
+                log::warn!("{}, updating on the CPU instead", err);
+                world.update();
+            }
+        }
+    }
+
+    // `Troops` is already `u32` with `wide-troops`, which never gets here.
+    #[allow(clippy::unnecessary_cast)]
+    fn step(&mut self, world: &mut World) -> Result<(), GpuError> {
+        self.prepare(world);
+        let buffers = self.buffers.as_ref().unwrap();
+
+        let topology = match world.topology {
+            Topology::Torus => 0u32,
+            Topology::Bounded => 1,
+        };
+        // Padded to the 16 byte alignment of uniform structs.
+        let params = [
+            world.width as u32,
+            world.height as u32,
+            topology,
+            world.tick as u32,
+            world.seed as u32,
+            (world.seed >> 32) as u32,
+            world.params.decay.to_bits(),
+            world.params.takeover_min.to_bits(),
+            world.params.takeover_max.to_bits(),
+            world.params.max_troops as u32,
+            0,
+            0,
+        ];
+        self.queue
+            .write_buffer(&buffers.params, 0, &to_bytes(params.into_iter()));
+        self.queue.write_buffer(
+            &buffers.cells_in,
+            0,
+            &to_bytes(
+                world
+                    .cells
+                    .iter()
+                    .map(|cell| cell.owner as u32 | (cell.troops as u32) << 16),
+            ),
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &buffers.bind_group, &[]);
+            pass.dispatch_workgroups(
+                (world.width as u32).div_ceil(WORKGROUP),
+                (world.height as u32).div_ceil(WORKGROUP),
+                1,
+            );
+        }
+        let size = (buffers.len * 4) as u64;
+        encoder.copy_buffer_to_buffer(&buffers.cells_out, 0, &buffers.readback, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffers.readback.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|_| GpuError::Readback)?
+            .map_err(|_| GpuError::Readback)?;
+
+        {
+            let data = slice.get_mapped_range();
+            for (i, word) in data.chunks_exact(4).enumerate() {
+                let packed = u32::from_le_bytes(word.try_into().unwrap());
+                world.cells.set(
+                    i,
+                    Cell {
+                        owner: packed as u16,
+                        troops: (packed >> 16) as Troops,
+                    },
+                );
+            }
+        }
+        buffers.readback.unmap();
+
+        world.tick += 1;
+        Ok(())
+    }
+
+    /// (Re)creates the buffers if the world changed size, and uploads the
+    /// terrain if it changed.
+    fn prepare(&mut self, world: &World) {
+        let len = world.cells.len();
+        match &self.buffers {
+            Some(buffers) if buffers.len == len => {}
+            _ => self.buffers = Some(self.create_buffers(len)),
+        }
+        let buffers = self.buffers.as_mut().unwrap();
+
+        if buffers.terrain != world.terrain {
+            let defense = world.terrain.iter().map(|terrain| {
+                if terrain.claimable() {
+                    terrain.defense()
+                } else {
+                    -terrain.defense()
+                }
+            });
+            self.queue
+                .write_buffer(&buffers.defense, 0, &to_bytes(defense.map(f32::to_bits)));
+            buffers.terrain.clone_from(&world.terrain);
+        }
+    }
+
+    fn create_buffers(&self, len: usize) -> Buffers {
+        let storage = |label, usage| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (len * 4) as u64,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let params = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: &[0; 48],
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let cells_in = storage(
+            "cells_in",
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        let cells_out = storage(
+            "cells_out",
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let defense = storage(
+            "defense",
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        );
+        let readback = storage(
+            "readback",
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[&params, &cells_in, &cells_out, &defense]
+                .into_iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        Buffers {
+            len,
+            params,
+            cells_in,
+            cells_out,
+            defense,
+            readback,
+            bind_group,
+            // never matches real terrain, so the first update uploads it
+            terrain: vec![],
+        }
+    }
+}
+
+fn to_bytes(words: impl Iterator<Item = u32>) -> Vec<u8> {
+    words.flat_map(u32::to_le_bytes).collect()
+}
+
+#[derive(Debug)]
+pub enum GpuError {
+    /// no adapter that can run compute shaders
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+    /// couldn't read the cells back
+    Readback,
+}
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no GPU adapter with compute shader support"),
+            GpuError::RequestDevice(err) => write!(f, "couldn't open the GPU: {}", err),
+            GpuError::Readback => write!(f, "couldn't read the cells back from the GPU"),
+        }
+    }
+}
+impl Error for GpuError {}
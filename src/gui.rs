@@ -2,11 +2,23 @@ use egui::{ClippedPrimitive, Context, TexturesDelta};
 use egui_wgpu::renderer::{RenderPass, ScreenDescriptor};
 use itertools::Itertools;
 use pixels::{wgpu, Pixels, PixelsContext};
-use rand::Rng;
 use winit::event_loop::EventLoopWindowTarget;
 use winit::window::Window;
 
-use libterritory::world::{Cell, Empire, World};
+use libterritory::cells::Layout;
+use libterritory::events::Event;
+#[cfg(feature = "gpu")]
+use libterritory::gpu::GpuBackend;
+use libterritory::net::{Client, Server};
+use libterritory::neutral::GarrisonSource;
+use libterritory::replay::{Intervention, Player, Replay};
+use libterritory::scenario;
+#[cfg(feature = "scripting")]
+use libterritory::script::{ScriptRule, ScriptWatcher};
+use libterritory::spawn::{Spawn, SpawnStrategy};
+use libterritory::terrain::{Terrain, TerrainGenerator};
+use libterritory::traits::Faction;
+use libterritory::world::{Cell, Troops, World};
 
 /// Manages all state required for rendering egui over `Pixels`.
 pub(crate) struct Framework {
@@ -61,6 +73,11 @@ impl Framework {
         self.egui_state.on_event(&self.egui_ctx, event);
     }
 
+    /// Whether egui is using the mouse, so it shouldn't paint the world.
+    pub(crate) fn wants_pointer(&self) -> bool {
+        self.egui_ctx.wants_pointer_input()
+    }
+
     /// Resize egui.
     pub(crate) fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
@@ -127,21 +144,150 @@ impl Framework {
 /// Example application state. A real application will need a lot more state than this.
 pub struct Gui {
     pub playing: bool,
+    /// hatches territory cut off from its empire's main body
+    pub show_cut_off: bool,
+    /// the empire whose fog of war the world is drawn through, if any
+    view: Option<u16>,
     new_width: u32,
     new_height: u32,
+    scenario_path: String,
+    scenario_error: Option<String>,
+    spawn: Spawn,
+    terrain: TerrainGenerator,
+    /// the most troops generated garrisons hold
+    garrison: Troops,
+    /// what the next added empire is like
+    faction: Faction,
+    brush: Brush,
+    replay: ReplayPanel,
+    net: NetPanel,
+    /// set while simulating on the GPU
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuBackend>,
+    #[cfg(feature = "gpu")]
+    gpu_error: Option<String>,
+    #[cfg(feature = "scripting")]
+    script: ScriptEditor,
 }
 impl Gui {
     /// Create a `Gui`.
     fn new() -> Self {
         Self {
             playing: true,
+            show_cut_off: false,
+            view: None,
             new_width: 256,
             new_height: 256,
+            scenario_path: String::from("scenarios/duel.toml"),
+            scenario_error: None,
+            spawn: Spawn::default(),
+            terrain: TerrainGenerator::default(),
+            garrison: 2_000,
+            faction: Faction::default(),
+            brush: Brush {
+                enabled: false,
+                cell: Cell {
+                    owner: 1,
+                    troops: 10_000,
+                },
+                radius: 4,
+            },
+            replay: ReplayPanel::new(),
+            net: NetPanel::new(),
+            #[cfg(feature = "gpu")]
+            gpu: None,
+            #[cfg(feature = "gpu")]
+            gpu_error: None,
+            #[cfg(feature = "scripting")]
+            script: ScriptEditor::new(),
+        }
+    }
+
+    /// Advances the world a tick, on whichever backend is selected, or the
+    /// replay being played back, or as far as the multiplayer match has.
+    pub(crate) fn update_world(&mut self, world: &mut World) {
+        if let Some(client) = &mut self.net.client {
+            if let Err(e) = client.update(world) {
+                self.net.error = Some(e.to_string());
+                self.net.client = None;
+            }
+            return;
+        }
+
+        if let Some(player) = &mut self.replay.player {
+            if !player.finished() {
+                player.step();
+                *world = player.world().clone();
+            }
+            return;
+        }
+
+        #[cfg(feature = "gpu")]
+        if let Some(gpu) = &mut self.gpu {
+            gpu.update(world);
+        } else {
+            world.update();
+        }
+        #[cfg(not(feature = "gpu"))]
+        world.update();
+
+        if let Some(recording) = &mut self.replay.recording {
+            recording.tick(world);
+        }
+    }
+
+    /// The empire the world is drawn as seen by. In a multiplayer match, that's
+    /// always the player's own.
+    pub(crate) fn view(&self) -> Option<u16> {
+        self.net
+            .client
+            .as_ref()
+            .and_then(Client::empire)
+            .or(self.view)
+    }
+
+    /// Makes a change to the world, and records it if recording. Stops any
+    /// playback, carrying on live from where it was. In a multiplayer match,
+    /// it's sent to the server instead.
+    fn intervene(&mut self, world: &mut World, intervention: Intervention) {
+        if let Some(client) = &mut self.net.client {
+            client.command(intervention);
+            return;
+        }
+        self.replay.player = None;
+        match &mut self.replay.recording {
+            Some(recording) => recording.record(world, intervention),
+            None => world.apply(&intervention),
+        }
+    }
+
+    /// Paints the brush onto the world at `x`, `y`, if painting is on.
+    pub(crate) fn paint(&mut self, world: &mut World, x: usize, y: usize) {
+        if !self.brush.enabled || self.brush.cell.owner as usize > world.empires.len() {
+            return;
+        }
+        let mut cell = self.brush.cell;
+        if let Some(empire) = self.net.client.as_ref().and_then(Client::empire) {
+            cell.owner = empire;
         }
+        let paint = Intervention::Paint {
+            x,
+            y,
+            radius: self.brush.radius,
+            cell,
+        };
+        self.intervene(world, paint);
     }
 
     /// Create the UI using egui.
     fn ui(&mut self, ctx: &Context, world: &mut World, pixels: &mut Pixels) {
+        let events = world.active_events().map(Event::name).collect::<Vec<_>>();
+        if !events.is_empty() {
+            egui::TopBottomPanel::top("events").show(ctx, |ui| {
+                ui.vertical_centered(|ui| ui.heading(events.join(", ")));
+            });
+        }
+
         egui::Window::new("About").show(ctx, |ui| {
             ui.heading("Usage");
 			ui.label("To get started, press 'Add empire' in the world settings window a few times, then hit 'Randomize' and watch!");
@@ -153,32 +299,205 @@ impl Gui {
             ui.add(egui::Slider::new(&mut self.new_width, 0..=1024).text("width"));
             ui.add(egui::Slider::new(&mut self.new_height, 0..=1024).text("height"));
 
+            let mut layout = world.cells.layout();
+            egui::ComboBox::from_label("cell storage")
+                .selected_text(layout.name())
+                .show_ui(ui, |ui| {
+                    for option in Layout::ALL {
+                        ui.selectable_value(&mut layout, option, option.name());
+                    }
+                });
+            if layout != world.cells.layout() {
+                world.set_layout(layout);
+            }
+
+            #[cfg(feature = "gpu")]
+            {
+                let mut on_gpu = self.gpu.is_some();
+                ui.checkbox(&mut on_gpu, "Simulate on GPU");
+                if on_gpu && self.gpu.is_none() {
+                    match GpuBackend::new() {
+                        Ok(gpu) => {
+                            self.gpu = Some(gpu);
+                            self.gpu_error = None;
+                        }
+                        Err(e) => self.gpu_error = Some(e.to_string()),
+                    }
+                } else if !on_gpu {
+                    self.gpu = None;
+                }
+                if let Some(gpu) = &self.gpu {
+                    ui.label(format!("adapter: {}", gpu.adapter_name()));
+                }
+                if let Some(error) = &self.gpu_error {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+            }
+
+            ui.checkbox(&mut self.show_cut_off, "Highlight cut off territory");
+            let vision = egui::Slider::new(&mut world.params.vision, 0..=64).text("vision");
+            if ui.add(vision).changed() {
+                world.update_visibility();
+            }
+            let name = |view: Option<u16>| match view {
+                Some(id) => world
+                    .empires
+                    .get(id as usize - 1)
+                    .map_or_else(String::new, |empire| empire.name.clone()),
+                None => String::from("Everyone"),
+            };
+            egui::ComboBox::from_label("view as")
+                .selected_text(name(self.view))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.view, None, name(None));
+                    for empire in &world.empires {
+                        ui.selectable_value(&mut self.view, Some(empire.id), &empire.name);
+                    }
+                });
+            ui.checkbox(&mut world.check_parallel, "Check serial against parallel");
+            if let Some(tick) = world.parallel_mismatch {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    format!("serial and parallel updates differed at tick {}", tick),
+                );
+            }
+
             if ui.button("Resize").clicked() {
                 world.resize(self.new_width as usize, self.new_height as usize);
                 pixels.resize_buffer(self.new_width, self.new_height);
+                self.replay.stop();
             }
 
-            if ui.button("Add empire").clicked() {
-                world.empires.push(Empire {
-                    id: (world.empires.len() + 1) as u16,
-                    color: (rand::random(), rand::random(), rand::random(), 255),
-                });
+            ui.horizontal(|ui| {
+                if ui.button("Add empire").clicked() {
+                    let add = Intervention::AddEmpire {
+                        name: format!("Empire {}", world.empires.len() + 1),
+                        color: (rand::random(), rand::random(), rand::random(), 255),
+                        traits: self.faction.traits(),
+                    };
+                    self.intervene(world, add);
+                }
+                egui::ComboBox::from_id_source("faction")
+                    .selected_text(self.faction.name())
+                    .show_ui(ui, |ui| {
+                        for faction in Faction::ALL {
+                            ui.selectable_value(&mut self.faction, faction, faction.name());
+                        }
+                    });
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.scenario_path);
+                if ui.button("Load scenario").clicked() {
+                    match scenario::load_world(&self.scenario_path) {
+                        Ok(loaded) => {
+                            pixels.resize_buffer(loaded.width as u32, loaded.height as u32);
+                            *world = loaded;
+                            self.scenario_error = None;
+                            self.replay.stop();
+                        }
+                        Err(e) => self.scenario_error = Some(e.to_string()),
+                    }
+                }
+            });
+            if let Some(error) = &self.scenario_error {
+                ui.colored_label(egui::Color32::RED, error);
             }
         });
 
+        egui::Window::new("Terrain").show(ctx, |ui| {
+            let gen = &mut self.terrain;
+            let mut terrain = None;
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut gen.seed).prefix("seed "));
+                if ui.button("Random seed").clicked() {
+                    gen.seed = rand::random();
+                }
+            });
+            ui.add(egui::Slider::new(&mut gen.scale, 4.0..=512.0).text("scale"));
+            ui.add(egui::Slider::new(&mut gen.octaves, 1..=8).text("octaves"));
+            ui.add(egui::Slider::new(&mut gen.persistence, 0.0..=1.0).text("persistence"));
+            ui.add(egui::Slider::new(&mut gen.plains, 0.0..=1.0).text("plains from"));
+            ui.add(egui::Slider::new(&mut gen.hills, 0.0..=1.0).text("hills from"));
+            ui.add(egui::Slider::new(&mut gen.mountains, 0.0..=1.0).text("mountains from"));
+            ui.add(egui::Slider::new(&mut gen.rivers, 0..=64).text("rivers"));
+
+            ui.horizontal(|ui| {
+                if ui.button("Generate").clicked() {
+                    terrain = Some(gen.generate(world.width, world.height));
+                }
+                if ui.button("Flat").clicked() {
+                    terrain = Some(vec![Terrain::Plains; world.width * world.height]);
+                }
+            });
+            if let Some(terrain) = terrain {
+                self.intervene(world, Intervention::Terrain(terrain));
+            }
+
+            ui.separator();
+            let troops = self.garrison;
+            ui.add(
+                egui::Slider::new(&mut self.garrison, 0..=world.params.max_troops)
+                    .text("wild troops"),
+            );
+            let mut source = None;
+            ui.horizontal(|ui| {
+                if ui.button("Garrison by terrain").clicked() {
+                    source = Some(GarrisonSource::Terrain { troops });
+                }
+                if ui.button("Garrison by noise").clicked() {
+                    source = Some(GarrisonSource::Noise {
+                        troops,
+                        seed: rand::random(),
+                        scale: self.terrain.scale,
+                    });
+                }
+                if ui.button("No garrisons").clicked() {
+                    source = Some(GarrisonSource::Terrain { troops: 0 });
+                }
+            });
+            if let Some(source) = source {
+                let garrisons = source.generate(&world.terrain, world.width, world.height);
+                self.intervene(world, Intervention::Garrisons(garrisons));
+            }
+        });
+
+        egui::Window::new("Paint").show(ctx, |ui| {
+            let brush = &mut self.brush;
+            ui.checkbox(&mut brush.enabled, "Paint with the mouse");
+            ui.add(
+                egui::Slider::new(&mut brush.cell.owner, 0..=world.empires.len() as u16)
+                    .text("empire (0 clears)"),
+            );
+            ui.add(
+                egui::Slider::new(&mut brush.cell.troops, 0..=world.params.max_troops)
+                    .text("troops"),
+            );
+            ui.add(egui::Slider::new(&mut brush.radius, 0..=32).text("radius"));
+        });
+
         egui::Window::new("World Info").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("spawn strategy")
+                    .selected_text(self.spawn.strategy.name())
+                    .show_ui(ui, |ui| {
+                        for strategy in SpawnStrategy::ALL {
+                            ui.selectable_value(
+                                &mut self.spawn.strategy,
+                                strategy,
+                                strategy.name(),
+                            );
+                        }
+                    });
+                ui.checkbox(&mut self.spawn.fair, "Fair");
+            });
             if ui.button("Randomize").clicked() {
-                world.cells = vec![Cell::default(); world.width * world.height];
-                for empire in world.empires.clone() {
-                    world.set(
-                        rand::thread_rng().gen_range(0..world.width) as isize,
-                        rand::thread_rng().gen_range(0..world.height) as isize,
-                        Cell {
-                            owner: empire.id,
-                            troops: rand::random(),
-                        },
-                    );
-                }
+                let spawn = Intervention::Spawn {
+                    spawn: self.spawn,
+                    seed: rand::random(),
+                };
+                self.intervene(world, spawn);
             }
             if self.playing {
                 if ui.button("Pause").clicked() {
@@ -187,6 +506,25 @@ impl Gui {
             } else if ui.button("Play").clicked() {
                 self.playing = true;
             }
+            ui.label(format!(
+                "tick {}, checksum {:016x}",
+                world.tick,
+                world.checksum()
+            ));
+            if !world.rebellions.is_empty() {
+                ui.collapsing(format!("{} rebellions", world.rebellions.len()), |ui| {
+                    for rebellion in world.rebellions.iter().rev().take(10) {
+                        let name = |id: u16| &world.empires[id as usize - 1].name;
+                        ui.label(format!(
+                            "tick {}: {} cells of {} broke away as {}",
+                            rebellion.tick,
+                            rebellion.cells,
+                            name(rebellion.empire),
+                            name(rebellion.rebels)
+                        ));
+                    }
+                });
+            }
 
             egui::ScrollArea::vertical()
                 .max_height(300.0)
@@ -196,29 +534,14 @@ impl Gui {
                     let empires_sorted = world
                         .empires
                         .iter()
-                        .map(|empire| {
-                            let e = &empire;
-                            (
-                                empire,
-                                world.cells.iter().filter(|v| v.owner == e.id).count(),
-                                world
-                                    .cells
-                                    .iter()
-                                    .filter_map(|v| {
-                                        if v.owner == e.id {
-                                            Some(v.troops as usize)
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                    .sum::<usize>(),
-                            )
-                        })
+                        .zip(world.stats())
+                        .map(|(empire, stats)| (empire, stats.cells, stats.troops))
                         .sorted_by_key(|v| v.2)
                         .rev()
                         .collect::<Vec<_>>();
+                    let winner = world.winner();
                     for (i, (empire, cells, troops)) in empires_sorted.iter().enumerate() {
-                        ui.heading(format!("Empire {}", empire.id));
+                        ui.heading(&empire.name);
 
                         ui.label(format!(
                             "{}",
@@ -230,15 +553,7 @@ impl Gui {
                                 })
                                 .count()
                         ));
-                        if world
-                            .empires
-                            .iter()
-                            .filter(|v| {
-                                v.id != empire.id && empires_sorted[v.id as usize - 1].1 == 0
-                            })
-                            .count()
-                            == world.empires.len() - 1
-                        {
+                        if winner == Some(empire.id) {
                             ui.label("Winner winner chicken dinner");
                         }
                         ui.label(format!("This empire is #{} in troops", i + 1));
@@ -251,10 +566,10 @@ impl Gui {
                         ui.color_edit_button_rgba_premultiplied(&mut color);
 
                         colors[(empire.id - 1) as usize] = (
-                            (color[0] * 255.) as u8,
-                            (color[1] * 255.) as u8,
-                            (color[2] * 255.) as u8,
-                            (color[3] * 255.) as u8,
+                            (color[0] * 255.).round() as u8,
+                            (color[1] * 255.).round() as u8,
+                            (color[2] * 255.).round() as u8,
+                            (color[3] * 255.).round() as u8,
                         );
                         ui.label(format!("{} cells", cells));
                         ui.label(if *troops > 1_000_000_000 {
@@ -264,11 +579,328 @@ impl Gui {
                         } else {
                             format!("{} troops", troops)
                         });
+                        let tech = &world.params.tech;
+                        if tech.per_cell > 0 {
+                            ui.label(format!(
+                                "tier {} of {}, {} research",
+                                empire.tech.tier,
+                                tech.tiers.len(),
+                                empire.tech.research
+                            ));
+                        }
+                    }
+                    for (i, &color) in colors.iter().enumerate() {
+                        if world.empires[i].color != color {
+                            let id = world.empires[i].id;
+                            self.intervene(world, Intervention::SetColor { id, color });
+                        }
+                    }
+                });
+        });
+
+        self.replay.ui(ctx, world, pixels);
+        if self.net.ui(ctx, world) {
+            self.replay.stop();
+        }
+
+        #[cfg(feature = "scripting")]
+        self.script.ui(ctx, world);
+    }
+}
+
+/// Cells painted onto the world with the mouse.
+struct Brush {
+    enabled: bool,
+    cell: Cell,
+    radius: usize,
+}
+
+/// The "Replay" window, for recording matches and playing them back.
+struct ReplayPanel {
+    path: String,
+    recording: Option<Replay>,
+    /// set while playing back, which `Gui::update_world` steps instead of the world
+    player: Option<Player>,
+    error: Option<String>,
+}
+impl ReplayPanel {
+    fn new() -> Self {
+        Self {
+            path: String::from("match.replay"),
+            recording: None,
+            player: None,
+            error: None,
+        }
+    }
+
+    /// Stops recording and playing back, when the world is replaced.
+    fn stop(&mut self) {
+        self.recording = None;
+        self.player = None;
+    }
+
+    fn play(&mut self, replay: Replay, world: &mut World, pixels: &mut Pixels) {
+        let player = Player::new(replay);
+        *world = player.world().clone();
+        pixels.resize_buffer(world.width as u32, world.height as u32);
+        self.recording = None;
+        self.player = Some(player);
+    }
+
+    fn ui(&mut self, ctx: &Context, world: &mut World, pixels: &mut Pixels) {
+        egui::Window::new("Replay").show(ctx, |ui| {
+            ui.horizontal(|ui| match &self.recording {
+                Some(recording) => {
+                    let status = format!(
+                        "{} ticks, {} interventions",
+                        recording.end - recording.start(),
+                        recording.events.len()
+                    );
+                    if ui.button("Stop recording").clicked() {
+                        self.recording = None;
+                    }
+                    ui.label(status);
+                }
+                None => {
+                    if ui.button("Record").clicked() {
+                        self.player = None;
+                        self.recording = Some(Replay::new(world));
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.path);
+                if ui.button("Save").clicked() {
+                    let replay = match (&self.recording, &self.player) {
+                        (Some(recording), _) => Some(recording),
+                        (None, Some(player)) => Some(player.replay()),
+                        (None, None) => None,
+                    };
+                    self.error = match replay.map(|replay| replay.save(&self.path)) {
+                        Some(Err(e)) => Some(format!("{}: {}", self.path, e)),
+                        Some(Ok(())) => None,
+                        None => Some("nothing recorded yet".into()),
+                    };
+                }
+                if ui.button("Load").clicked() {
+                    match Replay::load(&self.path) {
+                        Ok(replay) => {
+                            self.play(replay, world, pixels);
+                            self.error = None;
+                        }
+                        Err(e) => self.error = Some(format!("{}: {}", self.path, e)),
+                    }
+                }
+            });
+            if let Some(recording) = &self.recording {
+                if ui.button("Play back the recording").clicked() {
+                    let replay = recording.clone();
+                    self.play(replay, world, pixels);
+                }
+            }
+
+            if let Some(player) = &mut self.player {
+                ui.separator();
+                let mut tick = player.tick();
+                let range = player.replay().start()..=player.replay().end;
+                ui.add(egui::Slider::new(&mut tick, range).text("tick"));
+                if tick != player.tick() {
+                    player.seek(tick);
+                    *world = player.world().clone();
+                }
+                if let Some(tick) = player.desync() {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("doesn't match the recording since tick {}", tick),
+                    );
+                }
+                ui.label("Changing the world takes over from the replay.");
+                if ui.button("Stop playback").clicked() {
+                    self.player = None;
+                }
+            }
+
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+    }
+}
+
+/// The "Multiplayer" window, for hosting or joining a match over TCP.
+struct NetPanel {
+    address: String,
+    players: usize,
+    /// set while in a match
+    client: Option<Client>,
+    error: Option<String>,
+}
+impl NetPanel {
+    fn new() -> Self {
+        Self {
+            address: String::from("127.0.0.1:7878"),
+            players: 2,
+            client: None,
+            error: None,
+        }
+    }
+
+    /// Hosts a match of `world` and joins it as the first player.
+    fn host(&mut self, world: &World) -> Result<Client, String> {
+        let server = Server::bind(&self.address, world, self.players).map_err(|e| e.to_string())?;
+        let addr = server.local_addr().map_err(|e| e.to_string())?;
+        std::thread::spawn(move || {
+            if let Err(e) = server.run() {
+                log::warn!("multiplayer server stopped: {}", e);
+            }
+        });
+        Client::connect(addr).map_err(|e| e.to_string())
+    }
+
+    /// Returns whether a match was just joined.
+    fn ui(&mut self, ctx: &Context, world: &World) -> bool {
+        let mut joined = false;
+        egui::Window::new("Multiplayer").show(ctx, |ui| {
+            if let Some(client) = &self.client {
+                match client.empire() {
+                    Some(empire) => ui.label(format!(
+                        "playing as {}",
+                        world.empires[empire as usize - 1].name
+                    )),
+                    None => ui.label("waiting for the other players"),
+                };
+                ui.label("Painting only paints your empire.");
+                if ui.button("Leave").clicked() {
+                    self.client = None;
+                }
+            } else {
+                ui.text_edit_singleline(&mut self.address);
+                ui.add(
+                    egui::Slider::new(&mut self.players, 1..=world.empires.len().max(1))
+                        .text("players"),
+                );
+                ui.horizontal(|ui| {
+                    let client = if ui.button("Host").clicked() {
+                        Some(self.host(world))
+                    } else if ui.button("Join").clicked() {
+                        Some(Client::connect(&self.address).map_err(|e| e.to_string()))
+                    } else {
+                        None
+                    };
+                    match client {
+                        Some(Ok(client)) => {
+                            self.client = Some(client);
+                            self.error = None;
+                            joined = true;
+                        }
+                        Some(Err(e)) => self.error = Some(e),
+                        None => (),
                     }
-                    for (i, color) in colors.iter().enumerate() {
-                        world.empires[i].color = *color;
+                });
+            }
+
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+        joined
+    }
+}
+
+/// The "Script" window, for editing and hot reloading a `ScriptRule`.
+#[cfg(feature = "scripting")]
+struct ScriptEditor {
+    path: String,
+    source: String,
+    /// set while the file at `path` is being watched
+    watcher: Option<ScriptWatcher>,
+    error: Option<String>,
+}
+#[cfg(feature = "scripting")]
+impl ScriptEditor {
+    fn new() -> Self {
+        Self {
+            path: String::from("scripts/default.rhai"),
+            source: String::new(),
+            watcher: None,
+            error: None,
+        }
+    }
+
+    fn ui(&mut self, ctx: &Context, world: &mut World) {
+        if let Some(watcher) = &mut self.watcher {
+            match watcher.poll() {
+                Some(Ok(script)) => {
+                    self.source = script.source().to_owned();
+                    world.script = Some(std::sync::Arc::new(script));
+                    self.error = None;
+                }
+                Some(Err(e)) => self.error = Some(e.to_string()),
+                None => (),
+            }
+        }
+
+        egui::Window::new("Script").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.path);
+                if ui.button("Watch").clicked() {
+                    self.watcher = Some(ScriptWatcher::new(&self.path));
+                }
+                // A watched file gets picked up again on the next frame.
+                if ui.button("Save").clicked() {
+                    if let Err(e) = std::fs::write(&self.path, &self.source) {
+                        self.error = Some(format!("{}: {}", self.path, e));
                     }
+                }
+            });
+            if let Some(watcher) = &self.watcher {
+                ui.label(format!(
+                    "reloading {} when it changes",
+                    watcher.path().display()
+                ));
+            }
+
+            egui::ScrollArea::vertical()
+                .max_height(400.0)
+                .show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.source)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
                 });
+
+            ui.horizontal(|ui| {
+                if ui.button("Apply").clicked() {
+                    match ScriptRule::new(&self.source) {
+                        Ok(script) => {
+                            world.script = Some(std::sync::Arc::new(script));
+                            self.error = None;
+                        }
+                        Err(e) => self.error = Some(e.to_string()),
+                    }
+                }
+                if ui.button("Built-in rules").clicked() {
+                    world.script = None;
+                    self.watcher = None;
+                }
+            });
+
+            match &world.script {
+                Some(script) => {
+                    ui.label("running the script");
+                    if let Some(error) = script.error() {
+                        ui.colored_label(egui::Color32::RED, error);
+                    }
+                }
+                None => {
+                    ui.label("running the built-in rules");
+                }
+            }
+            if let Some(error) = &self.error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
         });
     }
 }
@@ -1 +1,31 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capi;
+pub mod cells;
+pub mod combat;
+pub mod events;
+pub mod flow;
+pub mod fog;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod net;
+pub mod neutral;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod rebellion;
+pub mod replay;
+mod rng;
+pub mod scenario;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod spawn;
+pub mod stats;
+pub mod supply;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sweep;
+pub mod tech;
+pub mod terrain;
+pub mod traits;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
 pub mod world;
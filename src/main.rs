@@ -7,7 +7,8 @@ use gui::Framework;
 use log::error;
 use pixels::{Error, Pixels, SurfaceTexture};
 
-use libterritory::world::{Cell, World};
+use libterritory::scenario;
+use libterritory::world::World;
 use winit::dpi::LogicalSize;
 use winit::event::{Event, VirtualKeyCode, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
@@ -18,6 +19,17 @@ const HEIGHT: u32 = 512;
 
 fn main() {
     env_logger::init();
+    let mut world = match std::env::args().nth(1) {
+        Some(path) => match scenario::load_world(&path) {
+            Ok(world) => world,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        None => World::new(WIDTH as usize, HEIGHT as usize),
+    };
+
     let event_loop = EventLoop::new();
     let mut input = WinitInputHelper::new();
     let window = {
@@ -33,7 +45,8 @@ fn main() {
     let (mut pixels, mut framework) = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        let pixels = Pixels::new(WIDTH, HEIGHT, surface_texture).expect("Pixels error");
+        let pixels = Pixels::new(world.width as u32, world.height as u32, surface_texture)
+            .expect("Pixels error");
 
         let framework = Framework::new(
             &event_loop,
@@ -46,7 +59,6 @@ fn main() {
         (pixels, framework)
     };
 
-    let mut world = World::new(WIDTH as usize, HEIGHT as usize);
     window.focus_window();
     let mut last_tick = Instant::now();
     event_loop.run(move |event, _, control_flow| {
@@ -61,7 +73,17 @@ fn main() {
             }
 
             Event::RedrawRequested(_) => {
-                world.draw(pixels.get_frame_mut());
+                // A multiplayer match can replace the world with another size.
+                if pixels.get_frame_mut().len() != world.cells.len() * 4 {
+                    pixels.resize_buffer(world.width as u32, world.height as u32);
+                }
+                match framework.gui.view() {
+                    Some(empire) => world.draw_for(empire, pixels.get_frame_mut()),
+                    None => world.draw(pixels.get_frame_mut()),
+                }
+                if framework.gui.show_cut_off {
+                    world.draw_cut_off(pixels.get_frame_mut());
+                }
                 // Prepare egui
                 framework.prepare(&window, &mut world, &mut pixels);
 
@@ -101,11 +123,21 @@ fn main() {
                 framework.resize(size.width, size.height);
             }
 
+            // Paint onto the world
+            if input.mouse_held(0) && !framework.wants_pointer() {
+                if let Some((x, y)) = input
+                    .mouse()
+                    .and_then(|pos| pixels.window_pos_to_pixel(pos).ok())
+                {
+                    framework.gui.paint(&mut world, x, y);
+                }
+            }
+
             // Update internal state and request a redraw
             if last_tick.elapsed().as_millis() >= 10 && framework.gui.playing {
                 last_tick = Instant::now();
 
-                world.update();
+                framework.gui.update_world(&mut world);
             }
 
             window.request_redraw();
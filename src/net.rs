@@ -0,0 +1,398 @@
+//! Lockstep multiplayer over TCP. Every player runs the same deterministic
+//! `World::update` and only commands are sent around: each tick, every client
+//! sends the server its player's commands and its world's checksum, and the
+//! server sends back everyone's commands once they're all in. If the checksums
+//! don't agree, the worlds have drifted apart and the match is stopped.
+//!
+//! ```no_run
+//! # use libterritory::{net::{Client, Server}, world::World};
+//! # let world = World::new(256, 256);
+//! let server = Server::bind("127.0.0.1:7878", &world, 2).unwrap();
+//! std::thread::spawn(move || server.run());
+//!
+//! let (mut client, mut world) = Client::join("127.0.0.1:7878").unwrap();
+//! loop {
+//!     client.step(&mut world).unwrap();
+//! }
+//! ```
+//!
+//! Player `n` controls empire `n`, in the order they joined. Players can paint
+//! their own empire's cells, with a radius of at most [`MAX_PAINT_RADIUS`] and
+//! no more than `params.max_troops`, and change its color (see
+//! [`Intervention`]), and the server drops anything else.
+use std::{
+    fmt,
+    io::{self, BufReader, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    replay::{Intervention, Snapshot},
+    world::{SimParams, World},
+};
+
+/// Messages bigger than this are taken to be garbage.
+const MAX_MESSAGE: usize = 64 << 20;
+/// The biggest brush a player may paint with.
+pub const MAX_PAINT_RADIUS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Message {
+    /// Server to client, when the match starts.
+    Welcome { empire: u16, world: Box<Snapshot> },
+    /// Client to server, a player's commands for `tick` and the checksum of
+    /// their world at it.
+    Commands {
+        tick: usize,
+        commands: Vec<Intervention>,
+        checksum: u64,
+    },
+    /// Server to client, everyone's commands for `tick`, made before updating.
+    Tick {
+        tick: usize,
+        commands: Vec<Intervention>,
+    },
+    /// Server to client, the checksums for `tick` didn't agree.
+    Desync { tick: usize },
+}
+
+pub struct Server {
+    listener: TcpListener,
+    world: Snapshot,
+    players: usize,
+}
+
+impl Server {
+    /// Hosts a match of `world` for `players` players.
+    pub fn bind(addr: impl ToSocketAddrs, world: &World, players: usize) -> Result<Self, NetError> {
+        if players == 0 || players > world.empires.len() {
+            return Err(NetError::Protocol(format!(
+                "{} players don't fit {} empires",
+                players,
+                world.empires.len()
+            )));
+        }
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            world: Snapshot::new(world),
+            players,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, NetError> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Waits for every player, then runs the match until they've all left.
+    pub fn run(self) -> Result<(), NetError> {
+        let (tx, rx) = mpsc::channel();
+        let mut writers = vec![];
+        for empire in 1..=self.players as u16 {
+            let (stream, addr) = self.listener.accept()?;
+            log::info!("{} joined as empire {}", addr, empire);
+            stream.set_nodelay(true)?;
+            let tx = tx.clone();
+            read_in_background(stream.try_clone()?, move |message| {
+                tx.send((empire, message)).is_ok()
+            });
+            writers.push(Some(stream));
+        }
+        drop(tx);
+
+        for (n, writer) in writers.iter_mut().enumerate() {
+            let welcome = Message::Welcome {
+                empire: n as u16 + 1,
+                world: Box::new(self.world.clone()),
+            };
+            send(writer, &welcome);
+        }
+
+        let mut tick = self.world.tick;
+        let mut turns = vec![None; self.players];
+        while let Ok((empire, message)) = rx.recv() {
+            let n = empire as usize - 1;
+            match message {
+                Ok(Message::Commands {
+                    tick: turn,
+                    commands,
+                    checksum,
+                }) if turn == tick => turns[n] = Some((checksum, commands)),
+                Ok(message) => {
+                    log::warn!("empire {} sent {:?}, dropping it", empire, message);
+                    disconnect(&mut writers[n]);
+                }
+                Err(e) => {
+                    log::info!("empire {} left: {}", empire, e);
+                    disconnect(&mut writers[n]);
+                }
+            }
+            if writers.iter().all(Option::is_none) {
+                break;
+            }
+
+            // Wait for everyone who's still here.
+            let ready = writers
+                .iter()
+                .zip(&turns)
+                .all(|(writer, turn)| writer.is_none() || turn.is_some());
+            if !ready {
+                continue;
+            }
+
+            let turns = std::mem::replace(&mut turns, vec![None; self.players]);
+            let mut checksums = turns.iter().flatten().map(|(checksum, _)| *checksum);
+            let first = checksums.next();
+            if checksums.any(|checksum| Some(checksum) != first) {
+                for writer in &mut writers {
+                    send(writer, &Message::Desync { tick });
+                    disconnect(writer);
+                }
+                return Err(NetError::Desync { tick });
+            }
+
+            let mut commands = vec![];
+            for (n, turn) in turns.into_iter().enumerate() {
+                let empire = n as u16 + 1;
+                for command in turn.map_or(vec![], |(_, commands)| commands) {
+                    if allowed(empire, &command, &self.world.params) {
+                        commands.push(command);
+                    } else {
+                        log::warn!("empire {} isn't allowed {:?}", empire, command);
+                    }
+                }
+            }
+            for writer in &mut writers {
+                send(
+                    writer,
+                    &Message::Tick {
+                        tick,
+                        commands: commands.clone(),
+                    },
+                );
+            }
+            tick += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `empire`'s player may make `command`.
+fn allowed(empire: u16, command: &Intervention, params: &SimParams) -> bool {
+    match command {
+        Intervention::Paint { radius, cell, .. } => {
+            cell.owner == empire && *radius <= MAX_PAINT_RADIUS && cell.troops <= params.max_troops
+        }
+        Intervention::SetColor { id, .. } => *id == empire,
+        _ => false,
+    }
+}
+
+/// Writes `message`, forgetting the stream if that fails.
+fn send(writer: &mut Option<TcpStream>, message: &Message) {
+    if let Some(stream) = writer {
+        if let Err(e) = write_message(stream, message) {
+            log::info!("couldn't send to {:?}: {}", stream.peer_addr(), e);
+            disconnect(writer);
+        }
+    }
+}
+
+/// Also stops the thread reading from it.
+fn disconnect(writer: &mut Option<TcpStream>) {
+    if let Some(stream) = writer.take() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
+
+pub struct Client {
+    stream: TcpStream,
+    incoming: Receiver<Result<Message, NetError>>,
+    /// set once the match has started
+    empire: Option<u16>,
+    commands: Vec<Intervention>,
+    /// the last tick this player's commands were sent for
+    sent: Option<usize>,
+}
+
+impl Client {
+    /// Connects to a server. The match starts once every player has joined,
+    /// which `update` replaces the world for.
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, NetError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let (tx, incoming) = mpsc::channel();
+        read_in_background(stream.try_clone()?, move |message| tx.send(message).is_ok());
+        Ok(Self {
+            stream,
+            incoming,
+            empire: None,
+            commands: vec![],
+            sent: None,
+        })
+    }
+
+    /// Connects and waits for the match to start, returning its world.
+    pub fn join(addr: impl ToSocketAddrs) -> Result<(Self, World), NetError> {
+        let mut client = Self::connect(addr)?;
+        let mut world = World::new(0, 0);
+        client.step(&mut world)?;
+        Ok((client, world))
+    }
+
+    /// The empire this player controls, once the match has started.
+    pub fn empire(&self) -> Option<u16> {
+        self.empire
+    }
+
+    /// Queues `command` to be made on the next tick, if the server allows it.
+    pub fn command(&mut self, command: Intervention) {
+        self.commands.push(command);
+    }
+
+    /// Moves the match on if the server has, without waiting. Returns whether
+    /// `world` changed.
+    pub fn update(&mut self, world: &mut World) -> Result<bool, NetError> {
+        self.send_commands(world)?;
+        match self.incoming.try_recv() {
+            Ok(message) => self.handle(world, message?).map(|()| true),
+            Err(TryRecvError::Empty) => Ok(false),
+            Err(TryRecvError::Disconnected) => Err(NetError::Disconnected),
+        }
+    }
+
+    /// Waits for the match to start, or to move on a tick.
+    pub fn step(&mut self, world: &mut World) -> Result<(), NetError> {
+        self.send_commands(world)?;
+        let message = self.incoming.recv().map_err(|_| NetError::Disconnected)?;
+        self.handle(world, message?)
+    }
+
+    fn send_commands(&mut self, world: &World) -> Result<(), NetError> {
+        if self.empire.is_none() || self.sent == Some(world.tick) {
+            return Ok(());
+        }
+        let message = Message::Commands {
+            tick: world.tick,
+            commands: std::mem::take(&mut self.commands),
+            checksum: world.checksum(),
+        };
+        write_message(&mut self.stream, &message)?;
+        self.sent = Some(world.tick);
+        Ok(())
+    }
+
+    fn handle(&mut self, world: &mut World, message: Message) -> Result<(), NetError> {
+        match message {
+            Message::Welcome {
+                empire,
+                world: snapshot,
+            } if self.empire.is_none() => {
+                *world = snapshot.to_world();
+                self.empire = Some(empire);
+                Ok(())
+            }
+            Message::Tick { tick, commands } if self.sent == Some(tick) && world.tick == tick => {
+                for command in &commands {
+                    world.apply(command);
+                }
+                world.update();
+                Ok(())
+            }
+            Message::Desync { tick } => Err(NetError::Desync { tick }),
+            message => Err(NetError::Protocol(format!(
+                "unexpected {:?} at tick {}",
+                message, world.tick
+            ))),
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Reads messages on another thread and hands them to `f` until it returns
+/// false or reading fails.
+fn read_in_background(
+    stream: TcpStream,
+    mut f: impl FnMut(Result<Message, NetError>) -> bool + Send + 'static,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        loop {
+            let message = read_message(&mut reader);
+            let failed = message.is_err();
+            if !f(message) || failed {
+                break;
+            }
+        }
+    });
+}
+
+/// Each message is its length as a little-endian `u32`, then MessagePack.
+fn write_message(writer: &mut impl Write, message: &Message) -> Result<(), NetError> {
+    let body = rmp_serde::to_vec(message)?;
+    let mut frame = (body.len() as u32).to_le_bytes().to_vec();
+    frame.extend(body);
+    writer.write_all(&frame)?;
+    Ok(())
+}
+
+fn read_message(reader: &mut impl Read) -> Result<Message, NetError> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE {
+        return Err(NetError::Protocol(format!("{} byte message", len)));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(rmp_serde::from_slice(&body)?)
+}
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    Encode(rmp_serde::encode::Error),
+    Decode(rmp_serde::decode::Error),
+    Protocol(String),
+    /// the players' worlds were different at `tick`
+    Desync {
+        tick: usize,
+    },
+    Disconnected,
+}
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "network error: {}", e),
+            NetError::Encode(e) => write!(f, "couldn't encode message: {}", e),
+            NetError::Decode(e) => write!(f, "couldn't decode message: {}", e),
+            NetError::Protocol(e) => write!(f, "protocol error: {}", e),
+            NetError::Desync { tick } => write!(f, "the worlds went out of sync at tick {}", tick),
+            NetError::Disconnected => write!(f, "disconnected"),
+        }
+    }
+}
+impl std::error::Error for NetError {}
+impl From<io::Error> for NetError {
+    fn from(e: io::Error) -> Self {
+        NetError::Io(e)
+    }
+}
+impl From<rmp_serde::encode::Error> for NetError {
+    fn from(e: rmp_serde::encode::Error) -> Self {
+        NetError::Encode(e)
+    }
+}
+impl From<rmp_serde::decode::Error> for NetError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        NetError::Decode(e)
+    }
+}
@@ -0,0 +1,81 @@
+//! Troops in unclaimed cells, so that the wild has to be fought for too. Each
+//! cell's garrison is in `World::garrisons`. Unclaimed cells never attack, and
+//! with `params.regrowth` they grow back toward their garrison when beaten
+//! down.
+//!
+//! ```toml
+//! [garrisons]
+//! type = "noise"
+//! troops = 3000
+//! seed = 4
+//! scale = 40.0
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    terrain::{Terrain, TerrainGenerator},
+    world::{Cell, Troops, World},
+};
+
+/// Where the garrisons of unclaimed cells come from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum GarrisonSource {
+    /// `troops` times each cell's terrain defense
+    Terrain { troops: Troops },
+    /// up to `troops`, following seeded fractal noise with features `scale`
+    /// cells across
+    Noise {
+        troops: Troops,
+        seed: u64,
+        scale: f32,
+    },
+}
+impl GarrisonSource {
+    /// Garrisons for a world with `terrain`. Cells that can't be claimed get
+    /// none.
+    pub fn generate(&self, terrain: &[Terrain], width: usize, height: usize) -> Vec<Troops> {
+        let strength = match self {
+            GarrisonSource::Terrain { .. } => terrain.iter().map(|t| t.defense()).collect(),
+            GarrisonSource::Noise { seed, scale, .. } => TerrainGenerator {
+                seed: *seed,
+                scale: *scale,
+                ..Default::default()
+            }
+            .elevation(width, height),
+        };
+        let troops = match *self {
+            GarrisonSource::Terrain { troops } | GarrisonSource::Noise { troops, .. } => troops,
+        };
+        strength
+            .into_iter()
+            .zip(terrain)
+            .map(|(strength, terrain)| {
+                if terrain.claimable() {
+                    (troops as f32 * strength) as Troops
+                } else {
+                    0
+                }
+            })
+            .collect()
+    }
+}
+
+impl World {
+    /// Empties every cell, leaving only the garrisons of the wild.
+    pub fn clear(&mut self) {
+        for i in 0..self.cells.len() {
+            let troops = self.garrison(i);
+            self.cells.set(i, Cell { owner: 0, troops });
+        }
+    }
+
+    /// The troops unclaimed cell `i` holds when left alone.
+    pub(crate) fn garrison(&self, i: usize) -> Troops {
+        if !self.terrain[i].claimable() {
+            return 0;
+        }
+        let garrison = self.garrisons.get(i).copied().unwrap_or(0);
+        garrison.min(self.params.max_troops)
+    }
+}
@@ -0,0 +1,494 @@
+//! Python bindings, built with `maturin develop --release` (see `pyproject.toml`).
+//!
+//! ```python
+//! import territory
+//!
+//! world = territory.World(256, 256, seed=1)
+//! world.add_empire("Red", (255, 0, 0))
+//! world.add_empire("Blue", (0, 0, 255))
+//! world.spawn("evenly_spaced", fair=True)
+//! winner = world.run(10_000)
+//! print(winner, world.owners.shape, world.stats())
+//! ```
+//!
+//! `World.owners` and `World.troops` are views of the world's own cells, which
+//! are kept as planes (see [`Layout::StructOfArrays`]) that never move.
+use std::{
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+};
+
+use numpy::{
+    ndarray::{Array2, ArrayView2},
+    Element, IntoPyArray, PyArray2, PyArrayMethods, PyReadonlyArray2,
+};
+use pyo3::{
+    exceptions::{PyIndexError, PyOSError, PyValueError},
+    prelude::*,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{de::IntoDeserializer, Deserialize};
+
+use crate::{
+    cells::{Layout, Storage},
+    scenario::{Scenario, ScenarioError},
+    spawn::{Spawn, SpawnStrategy},
+    world::{Cell, Empire, Topology, Troops, World},
+};
+
+#[pyclass(name = "Cell", eq, get_all, set_all)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PyCell {
+    pub owner: u16,
+    pub troops: Troops,
+}
+#[pymethods]
+impl PyCell {
+    #[new]
+    #[pyo3(signature = (owner = 0, troops = 0))]
+    fn new(owner: u16, troops: Troops) -> Self {
+        Self { owner, troops }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Cell(owner={}, troops={})", self.owner, self.troops)
+    }
+}
+
+/// A copy of one of `World.empires`, changing it doesn't change the world.
+#[pyclass(name = "Empire", get_all)]
+#[derive(Clone)]
+pub struct PyEmpire {
+    pub id: u16,
+    pub name: String,
+    pub color: (u8, u8, u8, u8),
+    pub research: u64,
+    pub tier: usize,
+}
+#[pymethods]
+impl PyEmpire {
+    fn __repr__(&self) -> String {
+        format!("Empire(id={}, name={:?})", self.id, self.name)
+    }
+}
+
+#[pyclass(name = "World")]
+#[derive(Clone)]
+pub struct PyWorld {
+    world: World,
+}
+
+#[pymethods]
+impl PyWorld {
+    #[new]
+    #[pyo3(signature = (width, height, seed = None))]
+    fn new(width: usize, height: usize, seed: Option<u64>) -> Self {
+        let mut world = World::new(width, height);
+        if let Some(seed) = seed {
+            world.seed = seed;
+        }
+        Self::from_world(world)
+    }
+
+    /// Builds the world described by a scenario file.
+    #[staticmethod]
+    fn load(path: PathBuf) -> PyResult<Self> {
+        Ok(Self::from_world(Scenario::load(path)?.build()?))
+    }
+
+    /// Same as `load`, from the file's contents.
+    #[staticmethod]
+    fn from_toml(source: &str) -> PyResult<Self> {
+        Ok(Self::from_world(Scenario::from_toml(source)?.build()?))
+    }
+
+    /// An independent copy, for trying different things from the same state.
+    fn copy(&self) -> Self {
+        self.clone()
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.world.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.world.height
+    }
+
+    #[getter]
+    fn tick(&self) -> usize {
+        self.world.tick
+    }
+
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.world.seed
+    }
+
+    #[setter]
+    fn set_seed(&mut self, seed: u64) {
+        self.world.seed = seed;
+    }
+
+    /// "torus" or "bounded"
+    #[getter]
+    fn topology(&self) -> &'static str {
+        match self.world.topology {
+            Topology::Torus => "torus",
+            Topology::Bounded => "bounded",
+        }
+    }
+
+    #[setter]
+    fn set_topology(&mut self, topology: &str) -> PyResult<()> {
+        self.world.topology = parse(topology)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn decay(&self) -> f32 {
+        self.world.params.decay
+    }
+
+    #[setter]
+    fn set_decay(&mut self, decay: f32) {
+        self.world.params.decay = decay;
+    }
+
+    /// how far empires see around their territory, 0 for no fog of war
+    #[getter]
+    fn vision(&self) -> usize {
+        self.world.params.vision
+    }
+
+    #[setter]
+    fn set_vision(&mut self, vision: usize) {
+        self.world.params.vision = vision;
+        self.world.update_visibility();
+    }
+
+    #[getter]
+    fn takeover_min(&self) -> f32 {
+        self.world.params.takeover_min
+    }
+
+    #[setter]
+    fn set_takeover_min(&mut self, takeover_min: f32) {
+        self.world.params.takeover_min = takeover_min;
+    }
+
+    #[getter]
+    fn takeover_max(&self) -> f32 {
+        self.world.params.takeover_max
+    }
+
+    #[setter]
+    fn set_takeover_max(&mut self, takeover_max: f32) {
+        self.world.params.takeover_max = takeover_max;
+    }
+
+    /// Most troops a cell can hold, troops saturate at it.
+    #[getter]
+    fn max_troops(&self) -> Troops {
+        self.world.params.max_troops
+    }
+
+    #[setter]
+    fn set_max_troops(&mut self, max_troops: Troops) {
+        self.world.params.max_troops = max_troops;
+    }
+
+    #[getter]
+    fn empires(&self) -> Vec<PyEmpire> {
+        self.world
+            .empires
+            .iter()
+            .map(|empire| PyEmpire {
+                id: empire.id,
+                name: empire.name.clone(),
+                color: empire.color,
+                research: empire.tech.research,
+                tier: empire.tech.tier,
+            })
+            .collect()
+    }
+
+    /// Returns the new empire's id.
+    fn add_empire(&mut self, name: String, color: (u8, u8, u8)) -> u16 {
+        let id = (self.world.empires.len() + 1) as u16;
+        self.world
+            .empires
+            .push(Empire::new(id, name, (color.0, color.1, color.2, 255)));
+        id
+    }
+
+    /// Clears the world and places every empire. `strategy` is one of "random",
+    /// "evenly_spaced", "grid", "circle", "corners" or "territories".
+    #[pyo3(signature = (strategy = "random", fair = false, seed = 0))]
+    fn spawn(&mut self, strategy: &str, fair: bool, seed: u64) -> PyResult<()> {
+        let strategy: SpawnStrategy = parse(strategy)?;
+        Spawn { strategy, fair }.apply(&mut self.world, &mut StdRng::seed_from_u64(seed));
+        Ok(())
+    }
+
+    /// Runs `ticks` ticks, letting other Python threads run in the meantime.
+    #[pyo3(signature = (ticks = 1))]
+    fn update(&mut self, py: Python, ticks: usize) {
+        self.detached(py, |world| {
+            for _ in 0..ticks {
+                world.update();
+            }
+        });
+    }
+
+    /// Runs until an empire wins or `max_ticks` have passed, returning the winner.
+    fn run(&mut self, py: Python, max_ticks: usize) -> Option<u16> {
+        self.detached(py, |world| {
+            for _ in 0..max_ticks {
+                if let Some(winner) = world.winner() {
+                    return Some(winner);
+                }
+                world.update();
+            }
+            world.winner()
+        })
+    }
+
+    fn winner(&self) -> Option<u16> {
+        self.world.winner()
+    }
+
+    /// A hash of the tick, cells and empires, the same for worlds that play out
+    /// the same way.
+    fn checksum(&self) -> u64 {
+        self.world.checksum()
+    }
+
+    /// `(id, cells, troops, research, tier)` for each empire.
+    fn stats(&self) -> Vec<(u16, usize, u64, u64, usize)> {
+        self.world
+            .stats()
+            .into_iter()
+            .map(|stats| {
+                (
+                    stats.id,
+                    stats.cells,
+                    stats.troops,
+                    stats.research,
+                    stats.tier,
+                )
+            })
+            .collect()
+    }
+
+    fn get(&self, x: usize, y: usize) -> PyResult<PyCell> {
+        let i = self.index(x, y)?;
+        let cell = self.world.cells.get(i);
+        Ok(PyCell {
+            owner: cell.owner,
+            troops: cell.troops,
+        })
+    }
+
+    fn set(&mut self, x: usize, y: usize, cell: PyCell) -> PyResult<()> {
+        let i = self.index(x, y)?;
+        self.check_owner(cell.owner)?;
+        self.world.cells.set(
+            i,
+            Cell {
+                owner: cell.owner,
+                troops: cell.troops,
+            },
+        );
+        Ok(())
+    }
+
+    /// Every cell's owner, as a read-only `(height, width)` view that follows
+    /// the world as it changes, without copying it. Assign an array to it to
+    /// change the world.
+    #[getter]
+    fn owners<'py>(slf: Bound<'py, Self>) -> Bound<'py, PyArray2<u16>> {
+        let this = slf.borrow();
+        let (owners, _) = this.planes();
+        let view = ArrayView2::from_shape((this.world.height, this.world.width), owners).unwrap();
+        // SAFETY: the planes never move while the world is alive (see
+        // `PyWorld::detached`), and the view keeps it alive.
+        readonly(unsafe { PyArray2::borrow_from_array(&view, slf.clone().into_any()) })
+    }
+
+    #[setter]
+    fn set_owners(&mut self, owners: PyReadonlyArray2<u16>) -> PyResult<()> {
+        // A copy, since it may be a view of these very cells.
+        let owners = owners.as_array().to_owned();
+        self.check_shape(owners.dim())?;
+        if let Some(&owner) = owners.iter().max() {
+            self.check_owner(owner)?;
+        }
+        for (i, &owner) in owners.iter().enumerate() {
+            let cell = self.world.cells.get(i);
+            self.world.cells.set(i, Cell { owner, ..cell });
+        }
+        Ok(())
+    }
+
+    /// Which cells `empire` can see, as a `(height, width)` array. Bots playing
+    /// an empire should only look at these.
+    fn visible<'py>(&self, py: Python<'py>, empire: u16) -> PyResult<Bound<'py, PyArray2<bool>>> {
+        self.check_owner(empire)?;
+        let values = (0..self.world.cells.len())
+            .map(|i| self.world.visible(empire, i))
+            .collect();
+        let visible =
+            Array2::from_shape_vec((self.world.height, self.world.width), values).unwrap();
+        Ok(visible.into_pyarray(py))
+    }
+
+    /// The owners and troops the way `empire` sees them, as `(height, width)`
+    /// arrays with 0 in both for the cells it can't see (see `visible`).
+    fn observe<'py>(&self, py: Python<'py>, empire: u16) -> PyResult<Arrays<'py>> {
+        self.check_owner(empire)?;
+        let shape = (self.world.height, self.world.width);
+        let (owners, troops) = self
+            .world
+            .observe(empire)
+            .into_iter()
+            .map(|cell| cell.map_or((0, 0), |cell| (cell.owner, cell.troops)))
+            .unzip();
+        Ok((
+            Array2::from_shape_vec(shape, owners)
+                .unwrap()
+                .into_pyarray(py),
+            Array2::from_shape_vec(shape, troops)
+                .unwrap()
+                .into_pyarray(py),
+        ))
+    }
+
+    /// Every cell's troops, as a read-only view like `owners`.
+    #[getter]
+    fn troops<'py>(slf: Bound<'py, Self>) -> Bound<'py, PyArray2<Troops>> {
+        let this = slf.borrow();
+        let (_, troops) = this.planes();
+        let view = ArrayView2::from_shape((this.world.height, this.world.width), troops).unwrap();
+        // SAFETY: as for `owners`.
+        readonly(unsafe { PyArray2::borrow_from_array(&view, slf.clone().into_any()) })
+    }
+
+    #[setter]
+    fn set_troops(&mut self, troops: PyReadonlyArray2<Troops>) -> PyResult<()> {
+        let troops = troops.as_array().to_owned();
+        self.check_shape(troops.dim())?;
+        for (i, &troops) in troops.iter().enumerate() {
+            let cell = self.world.cells.get(i);
+            self.world.cells.set(i, Cell { troops, ..cell });
+        }
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "World({}x{}, tick={}, empires={})",
+            self.world.width,
+            self.world.height,
+            self.world.tick,
+            self.world.empires.len()
+        )
+    }
+}
+
+impl PyWorld {
+    fn from_world(mut world: World) -> Self {
+        world.set_layout(Layout::StructOfArrays);
+        Self { world }
+    }
+
+    /// The owner and troop planes that `owners` and `troops` are views of.
+    fn planes(&self) -> (&[u16], &[Troops]) {
+        match &self.world.cells.storage {
+            Storage::Soa { owners, troops } => (owners, troops),
+            Storage::Aos(_) => unreachable!("the cells are always stored as planes"),
+        }
+    }
+
+    /// Runs `f` with the GIL released. Updates swap the cells with a second
+    /// buffer, so `f` gets a copy of them, which is copied back into the planes
+    /// that the views look at once it's done. Even if it panics, the planes stay.
+    fn detached<T: Send>(&mut self, py: Python, f: impl FnOnce(&mut World) -> T + Send) -> T {
+        let copy = self.world.cells.clone();
+        let mut planes = std::mem::replace(&mut self.world.cells, copy);
+        let world = &mut self.world;
+        let result = py.detach(|| panic::catch_unwind(AssertUnwindSafe(|| f(world))));
+        if result.is_ok() {
+            planes.copy_from(&self.world.cells);
+        }
+        self.world.cells = planes;
+        result.unwrap_or_else(|panic| panic::resume_unwind(panic))
+    }
+
+    fn index(&self, x: usize, y: usize) -> PyResult<usize> {
+        if x < self.world.width && y < self.world.height {
+            Ok(y * self.world.width + x)
+        } else {
+            Err(PyIndexError::new_err(format!(
+                "({}, {}) is outside the {}x{} world",
+                x, y, self.world.width, self.world.height
+            )))
+        }
+    }
+
+    fn check_shape(&self, (rows, cols): (usize, usize)) -> PyResult<()> {
+        if (rows, cols) == (self.world.height, self.world.width) {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "expected a ({}, {}) array, got ({}, {})",
+                self.world.height, self.world.width, rows, cols
+            )))
+        }
+    }
+
+    fn check_owner(&self, owner: u16) -> PyResult<()> {
+        if owner as usize <= self.world.empires.len() {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "there is no empire {}",
+                owner
+            )))
+        }
+    }
+}
+
+/// Owners and troops, as `World.observe` returns them.
+type Arrays<'py> = (Bound<'py, PyArray2<u16>>, Bound<'py, PyArray2<Troops>>);
+
+/// `array`, which Python can't write to anymore.
+fn readonly<T: Element>(array: Bound<'_, PyArray2<T>>) -> Bound<'_, PyArray2<T>> {
+    array.readwrite().make_nonwriteable();
+    array
+}
+
+/// One of the snake_case names that scenario files use.
+fn parse<'de, T: Deserialize<'de>>(name: &'de str) -> PyResult<T> {
+    T::deserialize(name.into_deserializer())
+        .map_err(|err: serde::de::value::Error| PyValueError::new_err(err.to_string()))
+}
+
+impl From<ScenarioError> for PyErr {
+    fn from(err: ScenarioError) -> Self {
+        match err {
+            ScenarioError::Io(_) => PyOSError::new_err(err.to_string()),
+            _ => PyValueError::new_err(err.to_string()),
+        }
+    }
+}
+
+#[pymodule]
+fn territory(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyWorld>()?;
+    m.add_class::<PyCell>()?;
+    m.add_class::<PyEmpire>()?;
+    Ok(())
+}
@@ -0,0 +1,241 @@
+//! Empires breaking apart. Every `params.rebellion.interval` ticks, pockets cut
+//! off from their empire's main body (see [`crate::supply`]) and the far reaches
+//! of empires that have grown too big can rise up and become empires of their
+//! own.
+//!
+//! ```toml
+//! [params.rebellion]
+//! interval = 100
+//! pocket_cells = 50
+//! max_cells = 20000
+//! breakaway = 0.2
+//! ```
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rng::CellRng,
+    supply::NO_COMPONENT,
+    tech::Tech,
+    world::{Cell, Empire, World},
+};
+
+/// When regions rebel, part of `SimParams`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RebellionParams {
+    /// ticks between checks for rebellions, 0 for none at all
+    pub interval: usize,
+    /// cut off pockets of at least this many cells rebel, 0 for none
+    pub pocket_cells: usize,
+    /// empires whose main body has more cells than this are over-extended, 0
+    /// for no limit
+    pub max_cells: usize,
+    /// fraction of an over-extended empire's main body that breaks away, around
+    /// a random cell of it
+    pub breakaway: f32,
+}
+impl Default for RebellionParams {
+    fn default() -> Self {
+        Self {
+            interval: 0,
+            pocket_cells: 0,
+            max_cells: 0,
+            breakaway: 0.2,
+        }
+    }
+}
+
+/// A region that broke away from `empire` as the new empire `rebels`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rebellion {
+    pub tick: usize,
+    pub empire: u16,
+    pub rebels: u16,
+    pub cells: usize,
+}
+
+impl World {
+    /// Turns the regions that rebel this tick into new empires.
+    pub(crate) fn rebel(&mut self) {
+        let params = &self.params.rebellion;
+        if params.interval == 0 || !self.tick.is_multiple_of(params.interval) {
+            return;
+        }
+        let (pocket_cells, max_cells, breakaway) =
+            (params.pocket_cells, params.max_cells, params.breakaway);
+
+        let components = self.components();
+        let mut members = vec![vec![]; components.components.len()];
+        for (i, &label) in components.labels.iter().enumerate() {
+            if label != NO_COMPONENT {
+                members[label as usize].push(i);
+            }
+        }
+
+        let mut regions = vec![];
+        for (label, (component, cells)) in components.components.iter().zip(members).enumerate() {
+            if !component.main && pocket_cells > 0 && component.cells >= pocket_cells {
+                regions.push((component.owner, cells));
+            } else if component.main && max_cells > 0 && component.cells > max_cells {
+                let take = (component.cells as f32 * breakaway.min(1.0)) as usize;
+                let mut rng = CellRng::new(self.seed, self.tick, self.cells.len() + label);
+                let start = cells[rng.below(cells.len() as u32) as usize];
+                regions.push((component.owner, self.region(start, take)));
+            }
+        }
+
+        for (empire, cells) in regions {
+            if cells.is_empty() || self.empires.len() >= u16::MAX as usize {
+                continue;
+            }
+            // Cells can belong to an id without an empire, if they were set
+            // by hand, and there's no one for those to rebel against.
+            let Some(parent) = self.empires.get(empire as usize - 1) else {
+                continue;
+            };
+            let id = self.empires.len() as u16 + 1;
+            let rebels = Empire {
+                id,
+                name: format!("{} Rebels", parent.name),
+                color: rebel_color(parent.color, &self.empires),
+                traits: parent.traits,
+                // They keep what their parent knew, but start over on the rest.
+                tech: Tech {
+                    research: 0,
+                    ..parent.tech
+                },
+            };
+            log::info!(
+                "{} cells of {} rebelled as {} at tick {}",
+                cells.len(),
+                parent.name,
+                rebels.id,
+                self.tick
+            );
+            for &i in &cells {
+                let cell = self.cells.get(i);
+                self.cells.set(
+                    i,
+                    Cell {
+                        owner: rebels.id,
+                        ..cell
+                    },
+                );
+            }
+            self.rebellions.push(Rebellion {
+                tick: self.tick,
+                empire,
+                rebels: rebels.id,
+                cells: cells.len(),
+            });
+            self.empires.push(rebels);
+        }
+    }
+
+    /// Up to `size` cells of the same empire as `start`, the closest to it
+    /// first, going through that empire's territory.
+    fn region(&self, start: usize, size: usize) -> Vec<usize> {
+        let owner = self.cells.get(start).owner;
+        let mut seen = vec![false; self.cells.len()];
+        let mut region = vec![];
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(i) = queue.pop_front() {
+            if region.len() >= size {
+                break;
+            }
+            region.push(i);
+            for j in self.neighbor_indices(i).into_iter().flatten() {
+                if !seen[j] && self.cells.get(j).owner == owner {
+                    seen[j] = true;
+                    queue.push_back(j);
+                }
+            }
+        }
+        region
+    }
+}
+
+/// How far apart in RGB a rebel color tries to be from every empire's.
+const MIN_COLOR_DISTANCE: f32 = 64.0;
+
+/// A color for rebels against `parent`: its color turned around the hue wheel
+/// by golden angles, then in lighter, darker and more or less saturated shades
+/// if that isn't enough, until it is at least `MIN_COLOR_DISTANCE` from every
+/// empire's. If nothing is, the one furthest from its closest empire.
+fn rebel_color(parent: (u8, u8, u8, u8), empires: &[Empire]) -> (u8, u8, u8, u8) {
+    let (r, g, b, a) = parent;
+    let rgb = [r, g, b].map(|c| c as f32 / 255.0);
+    let (hue, saturation, value) = to_hsv(rgb);
+    // Grays have no hue to turn, so give them some.
+    let saturation = saturation.max(0.5);
+
+    let distance = |color: [f32; 3]| {
+        empires
+            .iter()
+            .map(|empire| {
+                let (r, g, b, _) = empire.color;
+                let other = [r, g, b].map(|c| c as f32);
+                (0..3)
+                    .map(|c| (color[c] - other[c]).powi(2))
+                    .sum::<f32>()
+                    .sqrt()
+            })
+            .fold(f32::INFINITY, f32::min)
+    };
+    let mut best = ([0.0; 3], f32::NEG_INFINITY);
+    let lighter = value + (1.0 - value) * 0.6;
+    let shades = [value, value * 0.6, lighter]
+        .into_iter()
+        .flat_map(|value| [(value, saturation), (value, 1.0 - saturation * 0.5)]);
+    'search: for (value, saturation) in shades {
+        // Bright enough to see against the terrain.
+        let value = value.max(0.4);
+        for turn in 1..=12 {
+            let hue = (hue + turn as f32 * 137.508).rem_euclid(360.0);
+            let color = from_hsv(hue, saturation, value).map(|c| (c * 255.0).round());
+            let distance = distance(color);
+            if distance > best.1 {
+                best = (color, distance);
+            }
+            if distance >= MIN_COLOR_DISTANCE {
+                break 'search;
+            }
+        }
+    }
+    let [r, g, b] = best.0.map(|c| c as u8);
+    (r, g, b, a)
+}
+
+/// Hue in degrees, saturation and value of an RGB color with channels in 0..=1.
+fn to_hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let range = max - r.min(g).min(b);
+    let hue = if range == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / range).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / range + 2.0)
+    } else {
+        60.0 * ((r - g) / range + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { range / max };
+    (hue, saturation, max)
+}
+
+fn from_hsv(hue: f32, saturation: f32, value: f32) -> [f32; 3] {
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let [r, g, b] = match (hue / 60.0) as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    [r, g, b].map(|c| c + value - chroma)
+}
@@ -0,0 +1,462 @@
+//! Recording and playing back matches. A [`Replay`] is the world as it was when
+//! recording started, plus every [`Intervention`] made since and the tick it was
+//! made on. Updates only depend on the world and its seed, so that's all it
+//! takes to play a match again exactly.
+//!
+//! ```no_run
+//! # use libterritory::{replay::{Intervention, Player, Replay}, world::{Cell, World}};
+//! # let mut world = World::new(64, 64);
+//! let mut replay = Replay::new(&world);
+//! for _ in 0..100 {
+//!     world.update();
+//!     replay.tick(&world);
+//! }
+//! let cell = Cell { owner: 1, troops: 1000 };
+//! replay.record(&mut world, Intervention::Paint { x: 10, y: 10, radius: 2, cell });
+//! replay.save("match.replay").unwrap();
+//!
+//! let mut player = Player::new(Replay::load("match.replay").unwrap());
+//! player.seek(50);
+//! ```
+//!
+//! Recordings keep the world's checksum every now and then, and playback checks
+//! that it still matches, see [`Player::desync`].
+//!
+//! Replays are saved as deflated MessagePack. The script a world runs (see
+//! [`crate::script`]) is kept in the snapshot, but changing it while recording
+//! isn't an intervention.
+#[cfg(feature = "scripting")]
+use std::sync::Arc;
+use std::{
+    fmt, fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rebellion::Rebellion,
+    spawn::Spawn,
+    terrain::Terrain,
+    traits::Traits,
+    world::{Cell, Empire, SimParams, Topology, Troops, VictoryCondition, World},
+};
+
+/// Start of every replay file, bumped when the format changes.
+const MAGIC: &[u8; 4] = b"TRP8";
+
+/// How many ticks apart `Replay::tick` records checksums.
+const CHECKSUM_EVERY: usize = 64;
+
+/// How many ticks apart `Player` keeps copies of the world to seek back to.
+const KEYFRAME_EVERY: usize = 256;
+
+/// Everything about a world that affects how it plays out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub topology: Topology,
+    pub params: SimParams,
+    pub seed: u64,
+    pub tick: usize,
+    pub terrain: Vec<Terrain>,
+    pub garrisons: Vec<Troops>,
+    pub owners: Vec<u16>,
+    pub troops: Vec<Troops>,
+    pub empires: Vec<Empire>,
+    pub victory: Vec<VictoryCondition>,
+    pub rebellions: Vec<Rebellion>,
+    /// the source of the world's script, if it had one
+    pub script: Option<String>,
+}
+impl Snapshot {
+    pub fn new(world: &World) -> Self {
+        #[cfg(feature = "scripting")]
+        let script = world
+            .script
+            .as_ref()
+            .map(|script| script.source().to_owned());
+        #[cfg(not(feature = "scripting"))]
+        let script = None;
+
+        Self {
+            width: world.width,
+            height: world.height,
+            topology: world.topology,
+            params: world.params.clone(),
+            seed: world.seed,
+            tick: world.tick,
+            terrain: world.terrain.clone(),
+            garrisons: world.garrisons.clone(),
+            owners: world.cells.iter().map(|cell| cell.owner).collect(),
+            troops: world.cells.iter().map(|cell| cell.troops).collect(),
+            empires: world.empires.clone(),
+            victory: world.victory.clone(),
+            rebellions: world.rebellions.clone(),
+            script,
+        }
+    }
+
+    /// The world again. Without the `scripting` feature, scripts are ignored.
+    pub fn to_world(&self) -> World {
+        let mut world = World::new(self.width, self.height);
+        world.topology = self.topology;
+        world.params = self.params.clone();
+        world.seed = self.seed;
+        world.tick = self.tick;
+        world.terrain = self.terrain.clone();
+        world.garrisons = self.garrisons.clone();
+        for (i, (&owner, &troops)) in self.owners.iter().zip(&self.troops).enumerate() {
+            world.cells.set(i, Cell { owner, troops });
+        }
+        world.empires = self.empires.clone();
+        world.victory = self.victory.clone();
+        world.rebellions = self.rebellions.clone();
+        #[cfg(feature = "scripting")]
+        {
+            world.script = self
+                .script
+                .as_deref()
+                .and_then(|source| crate::script::ScriptRule::new(source).ok())
+                .map(Arc::new);
+        }
+        world.update_visibility();
+        world
+    }
+}
+
+/// A change made to a world from outside of `World::update`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Intervention {
+    /// Sets the claimable cells in the square `radius` cells around `x`, `y`.
+    Paint {
+        x: usize,
+        y: usize,
+        radius: usize,
+        cell: Cell,
+    },
+    AddEmpire {
+        name: String,
+        color: (u8, u8, u8, u8),
+        traits: Traits,
+    },
+    SetColor {
+        id: u16,
+        color: (u8, u8, u8, u8),
+    },
+    /// replaces all of the terrain
+    Terrain(Vec<Terrain>),
+    /// replaces all of the garrisons, and the troops of unclaimed cells with them
+    Garrisons(Vec<Troops>),
+    /// [`Spawn::apply`] with a `StdRng` seeded with `seed`
+    Spawn {
+        spawn: Spawn,
+        seed: u64,
+    },
+}
+
+impl World {
+    /// Makes `intervention`, ignoring the parts of it that don't fit this world.
+    pub fn apply(&mut self, intervention: &Intervention) {
+        match intervention {
+            Intervention::Paint { x, y, radius, cell } => {
+                if cell.owner as usize > self.empires.len() {
+                    return;
+                }
+                let (x, y) = (*x, *y);
+                let end =
+                    |n: usize, size: usize| n.saturating_add(*radius).saturating_add(1).min(size);
+                for y in y.saturating_sub(*radius)..end(y, self.height) {
+                    for x in x.saturating_sub(*radius)..end(x, self.width) {
+                        let i = y * self.width + x;
+                        if self.terrain[i].claimable() {
+                            self.cells.set(i, *cell);
+                        }
+                    }
+                }
+            }
+            Intervention::AddEmpire {
+                name,
+                color,
+                traits,
+            } => {
+                let id = (self.empires.len() + 1) as u16;
+                self.empires.push(Empire {
+                    traits: *traits,
+                    ..Empire::new(id, name.clone(), *color)
+                });
+            }
+            Intervention::SetColor { id, color } => {
+                if let Some(empire) = self.empires.iter_mut().find(|empire| empire.id == *id) {
+                    empire.color = *color;
+                }
+            }
+            Intervention::Terrain(terrain) => {
+                if terrain.len() == self.terrain.len() {
+                    self.terrain = terrain.clone();
+                }
+            }
+            Intervention::Garrisons(garrisons) => {
+                if garrisons.len() == self.garrisons.len() {
+                    self.garrisons = garrisons.clone();
+                    for i in 0..self.cells.len() {
+                        if self.cells.get(i).owner == 0 {
+                            let troops = self.garrison(i);
+                            self.cells.set(i, Cell { owner: 0, troops });
+                        }
+                    }
+                }
+            }
+            Intervention::Spawn { spawn, seed } => {
+                spawn.apply(self, &mut StdRng::seed_from_u64(*seed));
+            }
+        }
+        self.update_visibility();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    /// Made before the update to `tick + 1`.
+    pub tick: usize,
+    pub intervention: Intervention,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub initial: Snapshot,
+    /// in the order they were made
+    pub events: Vec<Event>,
+    /// the last tick that was recorded
+    pub end: usize,
+    /// `(tick, World::checksum)` every `CHECKSUM_EVERY` ticks, taken after
+    /// updating to `tick` and before its interventions
+    pub checksums: Vec<(usize, u64)>,
+}
+
+impl Replay {
+    /// Starts recording `world` from its current state.
+    pub fn new(world: &World) -> Self {
+        Self {
+            initial: Snapshot::new(world),
+            events: vec![],
+            end: world.tick,
+            checksums: vec![],
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        self.initial.tick
+    }
+
+    /// Applies `intervention` to `world` and adds it to the replay.
+    pub fn record(&mut self, world: &mut World, intervention: Intervention) {
+        world.apply(&intervention);
+        self.end = self.end.max(world.tick);
+        self.events.push(Event {
+            tick: world.tick,
+            intervention,
+        });
+    }
+
+    /// Records that `world` was updated, call it after every update.
+    pub fn tick(&mut self, world: &World) {
+        self.end = world.tick;
+        let last = self
+            .checksums
+            .last()
+            .map_or(self.start(), |&(tick, _)| tick);
+        if world.tick >= last + CHECKSUM_EVERY {
+            self.checksums.push((world.tick, world.checksum()));
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ReplayError> {
+        let mut encoder = DeflateEncoder::new(MAGIC.to_vec(), Compression::default());
+        encoder.write_all(&rmp_serde::to_vec(self)?)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let data = bytes.strip_prefix(MAGIC).ok_or(ReplayError::NotAReplay)?;
+        let mut decoded = vec![];
+        DeflateDecoder::new(data).read_to_end(&mut decoded)?;
+        let replay: Self = rmp_serde::from_slice(&decoded)?;
+
+        let Snapshot {
+            width,
+            height,
+            terrain,
+            garrisons,
+            owners,
+            troops,
+            empires,
+            ..
+        } = &replay.initial;
+        let cells = width.checked_mul(*height).ok_or_else(|| {
+            ReplayError::Invalid(format!("a {}x{} world is too big", width, height))
+        })?;
+        if [terrain.len(), garrisons.len(), owners.len(), troops.len()] != [cells; 4] {
+            return Err(ReplayError::Invalid(
+                "the snapshot doesn't match its size".into(),
+            ));
+        }
+        if let Some(owner) = owners.iter().find(|&&owner| owner as usize > empires.len()) {
+            return Err(ReplayError::Invalid(format!(
+                "a cell belongs to empire {}, but there are only {}",
+                owner,
+                empires.len()
+            )));
+        }
+        Ok(replay)
+    }
+}
+
+/// Plays a replay back, and seeks through it in both directions.
+pub struct Player {
+    replay: Replay,
+    world: World,
+    /// the next event to make
+    next: usize,
+    /// copies of the world every `KEYFRAME_EVERY` ticks so far, with their `next`
+    keyframes: Vec<(World, usize)>,
+    desync: Option<usize>,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let mut player = Self {
+            world: replay.initial.to_world(),
+            replay,
+            next: 0,
+            keyframes: vec![],
+            desync: None,
+        };
+        player.apply_events();
+        player.keyframes.push((player.world.clone(), player.next));
+        player
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// The world at `self.tick()`, after that tick's interventions.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn tick(&self) -> usize {
+        self.world.tick
+    }
+
+    pub fn finished(&self) -> bool {
+        self.world.tick >= self.replay.end
+    }
+
+    /// The first tick seen so far where the world's checksum didn't match the
+    /// recording's, if any. From then on the playback isn't what was recorded.
+    pub fn desync(&self) -> Option<usize> {
+        self.desync
+    }
+
+    /// Moves to `tick`, clamped to the recorded ticks.
+    pub fn seek(&mut self, tick: usize) {
+        let tick = tick.clamp(self.replay.start(), self.replay.end);
+        if tick < self.world.tick {
+            let (world, next) = self
+                .keyframes
+                .iter()
+                .rev()
+                .find(|(world, _)| world.tick <= tick)
+                .expect("the first keyframe is at the start");
+            self.world = world.clone();
+            self.next = *next;
+        }
+        while self.world.tick < tick {
+            self.advance();
+        }
+    }
+
+    /// Moves a tick forward, unless the replay is over.
+    pub fn step(&mut self) {
+        self.seek(self.world.tick + 1);
+    }
+
+    fn advance(&mut self) {
+        self.world.update();
+
+        let tick = self.world.tick;
+        let checksums = &self.replay.checksums;
+        if let Ok(n) = checksums.binary_search_by_key(&tick, |&(tick, _)| tick) {
+            if checksums[n].1 != self.world.checksum() && self.desync.is_none_or(|t| tick < t) {
+                self.desync = Some(tick);
+            }
+        }
+        self.apply_events();
+
+        let last = self.keyframes.last().map_or(0, |(world, _)| world.tick);
+        if self.world.tick >= last + KEYFRAME_EVERY {
+            self.keyframes.push((self.world.clone(), self.next));
+        }
+    }
+
+    fn apply_events(&mut self) {
+        while let Some(event) = self.replay.events.get(self.next) {
+            if event.tick > self.world.tick {
+                break;
+            }
+            self.world.apply(&event.intervention);
+            self.next += 1;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Encode(rmp_serde::encode::Error),
+    Decode(rmp_serde::decode::Error),
+    NotAReplay,
+    Invalid(String),
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "couldn't read or write replay: {}", e),
+            ReplayError::Encode(e) => write!(f, "couldn't encode replay: {}", e),
+            ReplayError::Decode(e) => write!(f, "couldn't decode replay: {}", e),
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::Invalid(e) => write!(f, "invalid replay: {}", e),
+        }
+    }
+}
+impl std::error::Error for ReplayError {}
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+impl From<rmp_serde::encode::Error> for ReplayError {
+    fn from(e: rmp_serde::encode::Error) -> Self {
+        ReplayError::Encode(e)
+    }
+}
+impl From<rmp_serde::decode::Error> for ReplayError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        ReplayError::Decode(e)
+    }
+}
@@ -0,0 +1,46 @@
+//! Stateless per-cell randomness, so that a tick's outcome only depends on the
+//! world's seed and not on which thread updated which cell or in what order.
+//!
+//! Only uses 32-bit integer math, so shaders can produce the same numbers.
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CellRng(u32);
+impl CellRng {
+    pub(crate) fn new(seed: u64, tick: usize, index: usize) -> Self {
+        let mut state = hash(seed as u32 ^ hash((seed >> 32) as u32));
+        state = hash(state ^ tick as u32);
+        state = hash(state ^ index as u32);
+        Self(state)
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9);
+        hash(self.0)
+    }
+
+    /// 0..n, with a negligible bias for small `n`
+    pub(crate) fn below(&mut self, n: u32) -> u32 {
+        self.next_u32() % n
+    }
+
+    /// lo..hi
+    pub(crate) fn range(&mut self, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * ((self.next_u32() >> 8) as f32 / (1 << 24) as f32)
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i as u32 + 1) as usize);
+        }
+    }
+}
+
+/// lowbias32 by Chris Wellons
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
@@ -0,0 +1,336 @@
+//! Human-editable descriptions of a full setup, written in TOML.
+//!
+//! ```toml
+//! width = 256
+//! height = 256
+//! seed = 42
+//! topology = "bounded"
+//!
+//! [params]
+//! decay = 0.95
+//!
+//! [terrain]
+//! type = "ascii"
+//! rows = [
+//!     "....~~....",
+//!     "..n.~~..^.",
+//!     "....~~....",
+//! ]
+//!
+//! [[empires]]
+//! name = "Red"
+//! color = [255, 0, 0]
+//! start = [20, 128]
+//! troops = 40000
+//!
+//! [[empires]]
+//! name = "Blue"
+//! color = [0, 0, 255]
+//! start = [230, 128]
+//! troops = 40000
+//!
+//! [[victory]]
+//! type = "territory"
+//! share = 0.75
+//! ```
+//!
+//! Unclaimed cells can hold troops too, see [`crate::neutral`].
+//!
+//! Instead of giving every empire a `start` and `troops`, a `[spawn]` table
+//! (see [`Spawn`]) can place them all:
+//!
+//! ```toml
+//! [spawn]
+//! strategy = "circle"
+//! fair = true
+//! ```
+//!
+//! Misspelled keys are errors rather than silently left at their defaults, and
+//! [`Scenario::build`] rejects values that the rules can't make sense of.
+use std::{fmt, fs, io, path::Path};
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::Event,
+    neutral::GarrisonSource,
+    spawn::Spawn,
+    terrain::TerrainSource,
+    traits::{Faction, Traits},
+    world::{Cell, Empire, SimParams, Topology, Troops, VictoryCondition, World},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub width: usize,
+    pub height: usize,
+    /// random if not given
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub topology: Topology,
+    #[serde(default)]
+    pub params: SimParams,
+    #[serde(default)]
+    pub terrain: TerrainSource,
+    /// troops in the unclaimed cells, none if not given
+    #[serde(default)]
+    pub garrisons: Option<GarrisonSource>,
+    #[serde(default)]
+    pub empires: Vec<EmpireSpec>,
+    /// places the empires, instead of their own `start`s
+    #[serde(default)]
+    pub spawn: Option<Spawn>,
+    #[serde(default = "default_victory")]
+    pub victory: Vec<VictoryCondition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmpireSpec {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub color: [u8; 3],
+    #[serde(default)]
+    pub start: Option<[usize; 2]>,
+    #[serde(default)]
+    pub troops: Option<Troops>,
+    /// preset traits, see [`crate::traits`]
+    #[serde(default)]
+    pub faction: Option<Faction>,
+    /// traits of its own, instead of the faction's
+    #[serde(default)]
+    pub traits: Option<Traits>,
+}
+
+fn default_victory() -> Vec<VictoryCondition> {
+    vec![VictoryCondition::LastStanding]
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ScenarioError> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Creates a ready-to-run `World` from this scenario.
+    pub fn build(&self) -> Result<World, ScenarioError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(ScenarioError::Invalid(format!(
+                "the world is {}x{}, but must be at least 1x1",
+                self.width, self.height
+            )));
+        }
+        check_params(&self.params).map_err(ScenarioError::Invalid)?;
+        for condition in &self.victory {
+            if let VictoryCondition::Territory { share } = *condition {
+                check_fraction("the victory share", share).map_err(ScenarioError::Invalid)?;
+            }
+        }
+
+        let mut world = World::new(self.width, self.height);
+        if let Some(seed) = self.seed {
+            world.seed = seed;
+        }
+        world.topology = self.topology;
+        world.params = self.params.clone();
+        world.victory = self.victory.clone();
+        world.terrain = self
+            .terrain
+            .generate(self.width, self.height)
+            .map_err(ScenarioError::Invalid)?;
+        if let Some(garrisons) = &self.garrisons {
+            world.garrisons = garrisons.generate(&world.terrain, self.width, self.height);
+            world.clear();
+        }
+
+        for (i, spec) in self.empires.iter().enumerate() {
+            let id = (i + 1) as u16;
+            if let Some(traits) = &spec.traits {
+                check_traits(&format!("empire {}'s", id), traits)
+                    .map_err(ScenarioError::Invalid)?;
+            }
+            let name = spec
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("Empire {}", id));
+            world.empires.push(Empire {
+                traits: spec
+                    .traits
+                    .unwrap_or_else(|| spec.faction.unwrap_or_default().traits()),
+                ..Empire::new(id, name, (spec.color[0], spec.color[1], spec.color[2], 255))
+            });
+        }
+
+        if let Some(spawn) = &self.spawn {
+            if let Some(i) = self
+                .empires
+                .iter()
+                .position(|spec| spec.start.is_some() || spec.troops.is_some())
+            {
+                return Err(ScenarioError::Invalid(format!(
+                    "empire {} has a start or troops, but the [spawn] table places every empire",
+                    i + 1
+                )));
+            }
+            let mut rng = StdRng::seed_from_u64(world.seed);
+            spawn.apply(&mut world, &mut rng);
+            world.update_visibility();
+            return Ok(world);
+        }
+
+        for (i, spec) in self.empires.iter().enumerate() {
+            let id = (i + 1) as u16;
+            let ([x, y], troops) = match (spec.start, spec.troops) {
+                (Some(start), Some(troops)) => (start, troops),
+                _ => {
+                    return Err(ScenarioError::Invalid(format!(
+                        "empire {} needs a start and troops, or the scenario a [spawn] table",
+                        id
+                    )))
+                }
+            };
+            if x >= self.width || y >= self.height {
+                return Err(ScenarioError::Invalid(format!(
+                    "empire {} starts at ({}, {}), outside the {}x{} world",
+                    id, x, y, self.width, self.height
+                )));
+            }
+            if troops > world.params.max_troops {
+                return Err(ScenarioError::Invalid(format!(
+                    "empire {} starts with {} troops, more than max_troops ({})",
+                    id, troops, world.params.max_troops
+                )));
+            }
+            if !world.terrain[y * self.width + x].claimable() {
+                return Err(ScenarioError::Invalid(format!(
+                    "empire {} starts at ({}, {}), which can't be claimed",
+                    id, x, y
+                )));
+            }
+
+            world.set(x as isize, y as isize, Cell { owner: id, troops });
+        }
+
+        world.update_visibility();
+        Ok(world)
+    }
+}
+
+/// Rejects parameters that the rules can't make sense of.
+pub(crate) fn check_params(params: &SimParams) -> Result<(), String> {
+    check_fraction("decay", params.decay)?;
+    check_fraction("flow", params.flow)?;
+    check_fraction("attrition", params.attrition)?;
+    check_fraction("regrowth", params.regrowth)?;
+    check_fraction("rebellion.breakaway", params.rebellion.breakaway)?;
+    // Also rejects NaN, which no comparison holds for.
+    if !(params.takeover_min >= 0.0 && params.takeover_min <= params.takeover_max) {
+        return Err(format!(
+            "takeover_min ({}) must be at least 0 and at most takeover_max ({})",
+            params.takeover_min, params.takeover_max
+        ));
+    }
+    for scheduled in &params.events {
+        match scheduled.event {
+            Event::Winter { decay } => check_multiplier("a winter's decay", decay)?,
+            Event::Harvest { growth } => check_multiplier("a harvest's growth", growth)?,
+            Event::Plague { loss, .. } => check_fraction("a plague's loss", loss)?,
+        }
+    }
+    for (n, tier) in params.tech.tiers.iter().enumerate() {
+        check_traits(&format!("tech tier {}'s", n + 1), &tier.traits)?;
+    }
+    Ok(())
+}
+
+/// Rejects traits that would break the rules: attacks divide by `attack`, and
+/// nothing can be negative.
+fn check_traits(whose: &str, traits: &Traits) -> Result<(), String> {
+    let Traits {
+        attack,
+        defense,
+        decay,
+        growth,
+        affinities,
+    } = *traits;
+    if !(attack > 0.0 && attack.is_finite()) {
+        return Err(format!(
+            "{} attack is {}, but must be more than 0",
+            whose, attack
+        ));
+    }
+    for (name, value) in [
+        ("defense", defense),
+        ("decay", decay),
+        ("growth", growth),
+        ("plains affinity", affinities.plains),
+        ("hills affinity", affinities.hills),
+        ("mountains affinity", affinities.mountains),
+        ("river affinity", affinities.river),
+    ] {
+        check_multiplier(&format!("{} {}", whose, name), value)?;
+    }
+    Ok(())
+}
+
+fn check_multiplier(name: &str, value: f32) -> Result<(), String> {
+    if value >= 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is {}, but must be a number of at least 0",
+            name, value
+        ))
+    }
+}
+
+fn check_fraction(name: &str, value: f32) -> Result<(), String> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is {}, but must be between 0 and 1",
+            name, value
+        ))
+    }
+}
+
+/// Loads the scenario at `path` and builds its `World`.
+pub fn load_world(path: impl AsRef<Path>) -> Result<World, ScenarioError> {
+    Scenario::load(path)?.build()
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(e) => write!(f, "couldn't read scenario: {}", e),
+            ScenarioError::Parse(e) => write!(f, "couldn't parse scenario: {}", e),
+            ScenarioError::Invalid(e) => write!(f, "invalid scenario: {}", e),
+        }
+    }
+}
+impl std::error::Error for ScenarioError {}
+impl From<io::Error> for ScenarioError {
+    fn from(e: io::Error) -> Self {
+        ScenarioError::Io(e)
+    }
+}
+impl From<toml::de::Error> for ScenarioError {
+    fn from(e: toml::de::Error) -> Self {
+        ScenarioError::Parse(e)
+    }
+}
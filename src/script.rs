@@ -0,0 +1,234 @@
+//! Cell rules written in [Rhai](https://rhai.rs), for trying out ideas without
+//! recompiling. A script defines `update(cell, neighbors, rng)`, which is
+//! called for every cell on every tick and returns the cell's next state:
+//!
+//! ```rhai
+//! fn update(cell, neighbors, rng) {
+//!     cell.troops = (cell.troops * DECAY).to_int();
+//!     for d in rng.order() {
+//!         let n = neighbors[d];
+//!         if n != () && n.owner != 0 && n.troops > cell.troops * cell.defense {
+//!             return #{ owner: n.owner, troops: n.troops };
+//!         }
+//!     }
+//!     cell
+//! }
+//! ```
+//!
+//! `cell` has `owner`, `troops`, `x`, `y`, `defense` and `claimable`.
+//! `neighbors` are maps with `owner` and `troops`, in the order left, right,
+//! up, down, up-left, up-right, down-left, down-right, and `()` past the edge
+//! of a bounded world. `rng` has `float()`, `below(n)`, `range(lo, hi)` and
+//! `order()`, a shuffled list of the neighbor indices, and gives the same
+//! numbers for the same seed, tick and cell. The constants `TICK`, `EMPIRES`,
+//! `DECAY`, `TAKEOVER_MIN` and `TAKEOVER_MAX` are set too.
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::{
+    rng::CellRng,
+    world::{Cell, Troops, World},
+};
+
+/// Keeps a runaway script from hanging the simulation.
+const MAX_OPERATIONS: u64 = 100_000;
+
+pub struct ScriptRule {
+    engine: Engine,
+    ast: AST,
+    source: String,
+    /// the first error a cell ran into
+    error: Mutex<Option<String>>,
+}
+
+#[derive(Clone)]
+struct ScriptRng(CellRng);
+
+impl ScriptRule {
+    pub fn new(source: &str) -> Result<Self, ScriptError> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+            .register_type_with_name::<ScriptRng>("Rng")
+            .register_fn("float", |rng: &mut ScriptRng| rng.0.range(0.0, 1.0) as f64)
+            .register_fn("below", |rng: &mut ScriptRng, n: i64| {
+                rng.0.below(n.clamp(1, u32::MAX as i64) as u32) as i64
+            })
+            .register_fn("range", |rng: &mut ScriptRng, lo: f64, hi: f64| {
+                rng.0.range(lo as f32, hi as f32) as f64
+            })
+            .register_fn("order", |rng: &mut ScriptRng| {
+                World::neighbor_order(&mut rng.0)
+                    .into_iter()
+                    .map(|d| Dynamic::from(d as i64))
+                    .collect::<Array>()
+            });
+
+        let ast = engine
+            .compile(source)
+            .map_err(|e| ScriptError::Compile(e.to_string()))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "update" && f.params.len() == 3)
+        {
+            return Err(ScriptError::Compile(
+                "the script needs an `update(cell, neighbors, rng)` function".into(),
+            ));
+        }
+
+        Ok(Self {
+            engine,
+            ast,
+            source: source.to_owned(),
+            error: Mutex::new(None),
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScriptError> {
+        Self::new(&fs::read_to_string(path)?)
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The first runtime error, if any. Cells that fail keep the built-in rules.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Runs the script for cell `i`, or returns `None` if it failed.
+    pub(crate) fn next_cell(
+        &self,
+        world: &World,
+        i: usize,
+        neighbors: [Option<Cell>; 8],
+    ) -> Option<Cell> {
+        match self.run(world, i, neighbors) {
+            Ok(cell) => Some(cell),
+            Err(e) => {
+                self.error.lock().unwrap().get_or_insert(e);
+                None
+            }
+        }
+    }
+
+    fn run(&self, world: &World, i: usize, neighbors: [Option<Cell>; 8]) -> Result<Cell, String> {
+        let params = &world.params;
+        let mut scope = Scope::new();
+        scope
+            .push_constant("TICK", world.tick as i64)
+            .push_constant("EMPIRES", world.empires.len() as i64)
+            .push_constant("DECAY", params.decay as f64)
+            .push_constant("TAKEOVER_MIN", params.takeover_min as f64)
+            .push_constant("TAKEOVER_MAX", params.takeover_max as f64);
+
+        let mut cell = to_map(world.cells.get(i));
+        cell.insert("x".into(), ((i % world.width) as i64).into());
+        cell.insert("y".into(), ((i / world.width) as i64).into());
+        cell.insert("defense".into(), (world.terrain[i].defense() as f64).into());
+        cell.insert("claimable".into(), world.terrain[i].claimable().into());
+        let neighbors = neighbors
+            .into_iter()
+            .map(|neighbor| neighbor.map_or(Dynamic::UNIT, |n| to_map(n).into()))
+            .collect::<Array>();
+        let rng = ScriptRng(CellRng::new(world.seed, world.tick, i));
+
+        let next = self
+            .engine
+            .call_fn::<Dynamic>(&mut scope, &self.ast, "update", (cell, neighbors, rng))
+            .map_err(|e| e.to_string())?;
+        let next = next
+            .try_cast::<Map>()
+            .ok_or("`update` must return a map with `owner` and `troops`")?;
+
+        let field = |name: &str| {
+            next.get(name)
+                .and_then(|value| value.as_int().ok())
+                .ok_or(format!("`update` returned no integer `{}`", name))
+        };
+        let owner = field("owner")?;
+        if owner < 0 || owner as usize > world.empires.len() {
+            return Err(format!(
+                "`update` returned owner {}, which isn't an empire",
+                owner
+            ));
+        }
+        Ok(Cell {
+            owner: owner as u16,
+            troops: field("troops")?.clamp(0, world.params.max_troops as i64) as Troops,
+        })
+    }
+}
+
+fn to_map(cell: Cell) -> Map {
+    let mut map = Map::new();
+    map.insert("owner".into(), (cell.owner as i64).into());
+    map.insert("troops".into(), (cell.troops as i64).into());
+    map
+}
+
+/// Recompiles a script file whenever it changes on disk.
+pub struct ScriptWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    missing: bool,
+}
+impl ScriptWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            modified: None,
+            missing: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The newly compiled script if the file changed since the last call, which
+    /// the first call always counts as.
+    pub fn poll(&mut self) -> Option<Result<ScriptRule, ScriptError>> {
+        let modified = match fs::metadata(&self.path).and_then(|meta| meta.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                // Only reported once, until the file is back.
+                self.modified = None;
+                return (!std::mem::replace(&mut self.missing, true)).then(|| Err(e.into()));
+            }
+        };
+        self.missing = false;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        Some(ScriptRule::load(&self.path))
+    }
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(io::Error),
+    Compile(String),
+}
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(e) => write!(f, "couldn't read script: {}", e),
+            ScriptError::Compile(e) => write!(f, "couldn't compile script: {}", e),
+        }
+    }
+}
+impl std::error::Error for ScriptError {}
+impl From<io::Error> for ScriptError {
+    fn from(e: io::Error) -> Self {
+        ScriptError::Io(e)
+    }
+}
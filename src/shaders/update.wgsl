@@ -0,0 +1,117 @@
+// The same rules as `World::next_cell`, one invocation per cell.
+
+struct Params {
+    width: u32,
+    height: u32,
+    bounded: u32,
+    tick: u32,
+    seed_lo: u32,
+    seed_hi: u32,
+    decay: f32,
+    takeover_min: f32,
+    takeover_max: f32,
+    max_troops: u32,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+// owner in the low 16 bits, troops in the high 16 bits
+@group(0) @binding(1) var<storage, read> cells_in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> cells_out: array<u32>;
+// `Terrain::defense` of each cell, negative if it can't be claimed
+@group(0) @binding(3) var<storage, read> defense: array<f32>;
+
+// lowbias32, same as `rng::hash`
+fn hash(x_in: u32) -> u32 {
+    var x = x_in;
+    x = x ^ (x >> 16u);
+    x = x * 0x7feb352du;
+    x = x ^ (x >> 15u);
+    x = x * 0x846ca68bu;
+    x = x ^ (x >> 16u);
+    return x;
+}
+
+var<private> rng: u32;
+
+fn next_u32() -> u32 {
+    rng = rng + 0x9e3779b9u;
+    return hash(rng);
+}
+
+fn range(lo: f32, hi: f32) -> f32 {
+    return lo + (hi - lo) * (f32(next_u32() >> 8u) / 16777216.0);
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let i = id.y * params.width + id.x;
+
+    rng = hash(params.seed_lo ^ hash(params.seed_hi));
+    rng = hash(rng ^ params.tick);
+    rng = hash(rng ^ i);
+
+    var order = array<u32, 8>(0u, 1u, 2u, 3u, 4u, 5u, 6u, 7u);
+    var k = 7u;
+    loop {
+        if (k == 0u) {
+            break;
+        }
+        let j = next_u32() % (k + 1u);
+        let swap = order[k];
+        order[k] = order[j];
+        order[j] = swap;
+        k = k - 1u;
+    }
+
+    let here = cells_in[i];
+    var owner = here & 0xffffu;
+    var troops = min(u32(f32(here >> 16u) * params.decay), params.max_troops);
+
+    var dx = array<i32, 8>(-1, 1, 0, 0, -1, 1, -1, 1);
+    var dy = array<i32, 8>(0, 0, -1, 1, -1, -1, 1, 1);
+    let w = i32(params.width);
+    let h = i32(params.height);
+
+    for (var n = 0u; n < 8u; n = n + 1u) {
+        let d = order[n];
+        var x = i32(id.x) + dx[d];
+        var y = i32(id.y) + dy[d];
+        if (params.bounded != 0u) {
+            if (x < 0 || y < 0 || x >= w || y >= h) {
+                continue;
+            }
+        } else {
+            // `%` of negative numbers isn't portable, and neighbors are at most one step out.
+            x = select(x, x + w, x < 0);
+            x = select(x, x - w, x >= w);
+            y = select(y, y + h, y < 0);
+            y = select(y, y - h, y >= h);
+        }
+
+        let neighbor = cells_in[u32(y * w + x)];
+        let neighbor_owner = neighbor & 0xffffu;
+        let neighbor_troops = neighbor >> 16u;
+        var bar = f32(troops);
+        if (neighbor_owner != owner) {
+            bar = f32(troops) * abs(defense[i]);
+        }
+        if (f32(neighbor_troops) > bar) {
+            owner = neighbor_owner;
+            troops = min(
+                u32(f32(neighbor_troops) * range(params.takeover_min, params.takeover_max)),
+                params.max_troops
+            );
+            break;
+        }
+    }
+
+    if (owner == 0u || defense[i] < 0.0) {
+        owner = 0u;
+        troops = 0u;
+    }
+
+    cells_out[i] = owner | (troops << 16u);
+}
@@ -0,0 +1,299 @@
+//! Ways of placing each empire's starting cells.
+use std::f32::consts::TAU;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::world::{Cell, Topology, Troops, World};
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnStrategy {
+    /// uniformly random cells
+    #[default]
+    Random,
+    /// random cells kept a minimum distance apart (Poisson-disk sampling)
+    EvenlySpaced,
+    /// centers of a grid of equal blocks
+    Grid,
+    /// evenly spaced around a circle in the middle of the world
+    Circle,
+    /// corners, then edge midpoints, or evenly around the border past 8 empires
+    Corners,
+    /// the whole world is split into one Voronoi region per empire
+    Territories,
+}
+impl SpawnStrategy {
+    pub const ALL: [SpawnStrategy; 6] = [
+        SpawnStrategy::Random,
+        SpawnStrategy::EvenlySpaced,
+        SpawnStrategy::Grid,
+        SpawnStrategy::Circle,
+        SpawnStrategy::Corners,
+        SpawnStrategy::Territories,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnStrategy::Random => "Random",
+            SpawnStrategy::EvenlySpaced => "Evenly spaced",
+            SpawnStrategy::Grid => "Grid",
+            SpawnStrategy::Circle => "Circle",
+            SpawnStrategy::Corners => "Corners",
+            SpawnStrategy::Territories => "Territories",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spawn {
+    pub strategy: SpawnStrategy,
+    /// give every empire the same number of starting troops
+    pub fair: bool,
+}
+impl Spawn {
+    /// Clears the world's cells and places every empire in `world.empires`.
+    pub fn apply(&self, world: &mut World, rng: &mut impl Rng) {
+        world.clear();
+        if world.empires.is_empty() || world.cells.is_empty() {
+            return;
+        }
+
+        // Every empire starts with at least one troop, unless the cap is 0.
+        let max = world.params.max_troops;
+        let fair_troops = rng.gen_range(max.min(1)..=max);
+        let troops = world
+            .empires
+            .iter()
+            .map(|_| {
+                if self.fair {
+                    fair_troops
+                } else {
+                    rng.gen_range(max.min(1)..=max)
+                }
+            })
+            .collect::<Vec<Troops>>();
+
+        // Empires that would start on the same cell, like when there are more
+        // of them than a grid has blocks, move over to the nearest free one.
+        // Those that find none, in a world with fewer claimable cells than
+        // empires, don't start at all.
+        let mut taken = vec![false; world.cells.len()];
+        let points = self
+            .points(world, rng)
+            .into_iter()
+            .map(|(x, y)| {
+                let (x, y) = nearest_free(world, x, y, &taken)?;
+                taken[y * world.width + x] = true;
+                Some((x, y))
+            })
+            .collect::<Vec<_>>();
+
+        if self.strategy == SpawnStrategy::Territories {
+            for i in 0..world.cells.len() {
+                if !world.terrain[i].claimable() {
+                    continue;
+                }
+                let here = (i % world.width, i / world.width);
+                let Some((nearest, _)) = points
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(n, point)| Some((n, distance2(world, here, (*point)?))))
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                else {
+                    continue;
+                };
+                world.cells.set(
+                    i,
+                    Cell {
+                        owner: world.empires[nearest].id,
+                        troops: troops[nearest],
+                    },
+                );
+            }
+            return;
+        }
+
+        for (n, point) in points.into_iter().enumerate() {
+            if let Some((x, y)) = point {
+                world.set(
+                    x as isize,
+                    y as isize,
+                    Cell {
+                        owner: world.empires[n].id,
+                        troops: troops[n],
+                    },
+                );
+            }
+        }
+    }
+
+    /// One starting point per empire, in the same order as `world.empires`.
+    fn points(&self, world: &World, rng: &mut impl Rng) -> Vec<(usize, usize)> {
+        let n = world.empires.len();
+        let (w, h) = (world.width as f32, world.height as f32);
+        let at = |x: f32, y: f32| {
+            (
+                (x.max(0.0) as usize).min(world.width - 1),
+                (y.max(0.0) as usize).min(world.height - 1),
+            )
+        };
+
+        let mut points = match self.strategy {
+            SpawnStrategy::Random => (0..n)
+                .map(|_| {
+                    (
+                        rng.gen_range(0..world.width),
+                        rng.gen_range(0..world.height),
+                    )
+                })
+                .collect(),
+            SpawnStrategy::EvenlySpaced | SpawnStrategy::Territories => poisson_disk(world, n, rng),
+            SpawnStrategy::Grid => {
+                let cols = ((n as f32 * w / h).sqrt().ceil() as usize).max(1);
+                let rows = n.div_ceil(cols);
+                (0..n)
+                    .map(|i| {
+                        at(
+                            ((i % cols) as f32 + 0.5) * w / cols as f32,
+                            ((i / cols) as f32 + 0.5) * h / rows as f32,
+                        )
+                    })
+                    .collect()
+            }
+            SpawnStrategy::Circle => {
+                let radius = w.min(h) * 0.35;
+                let offset = rng.gen_range(0.0..TAU);
+                (0..n)
+                    .map(|i| {
+                        let angle = offset + TAU * i as f32 / n as f32;
+                        at(
+                            w / 2.0 + radius * angle.cos(),
+                            h / 2.0 + radius * angle.sin(),
+                        )
+                    })
+                    .collect()
+            }
+            SpawnStrategy::Corners => {
+                let (left, right) = (w / 8.0, w * 7.0 / 8.0);
+                let (top, bottom) = (h / 8.0, h * 7.0 / 8.0);
+                if n <= 8 {
+                    [
+                        (left, top),
+                        (right, bottom),
+                        (right, top),
+                        (left, bottom),
+                        (w / 2.0, top),
+                        (w / 2.0, bottom),
+                        (left, h / 2.0),
+                        (right, h / 2.0),
+                    ]
+                    .into_iter()
+                    .take(n)
+                    .map(|(x, y)| at(x, y))
+                    .collect()
+                } else {
+                    let (iw, ih) = (right - left, bottom - top);
+                    let perimeter = 2.0 * (iw + ih);
+                    (0..n)
+                        .map(|i| {
+                            let d = perimeter * i as f32 / n as f32;
+                            if d < iw {
+                                at(left + d, top)
+                            } else if d < iw + ih {
+                                at(right, top + d - iw)
+                            } else if d < 2.0 * iw + ih {
+                                at(right - (d - iw - ih), bottom)
+                            } else {
+                                at(left, bottom - (d - 2.0 * iw - ih))
+                            }
+                        })
+                        .collect()
+                }
+            }
+        };
+
+        // Keep which empire gets which spot from favoring low ids.
+        if self.strategy != SpawnStrategy::Random {
+            points.shuffle(rng);
+        }
+        points
+    }
+}
+
+/// Poisson-disk dart throwing, shrinking the minimum distance until `n` points fit.
+fn poisson_disk(world: &World, n: usize, rng: &mut impl Rng) -> Vec<(usize, usize)> {
+    let area = world
+        .terrain
+        .iter()
+        .filter(|t| t.claimable())
+        .count()
+        .max(1) as f32;
+    let mut radius = (area / n as f32).sqrt();
+
+    loop {
+        let mut points: Vec<(usize, usize)> = Vec::with_capacity(n);
+        for _ in 0..n * 30 {
+            let candidate = (
+                rng.gen_range(0..world.width),
+                rng.gen_range(0..world.height),
+            );
+            if !world.terrain[candidate.1 * world.width + candidate.0].claimable() {
+                continue;
+            }
+            if points
+                .iter()
+                .all(|&p| distance2(world, p, candidate) >= radius * radius)
+            {
+                points.push(candidate);
+                if points.len() == n {
+                    return points;
+                }
+            }
+        }
+        if radius < 1.0 {
+            // Not enough claimable cells to spread out, just fill up.
+            points.resize_with(n, || {
+                (
+                    rng.gen_range(0..world.width),
+                    rng.gen_range(0..world.height),
+                )
+            });
+            return points;
+        }
+        radius *= 0.9;
+    }
+}
+
+/// Squared distance between two cells, going across the edges if the world wraps.
+fn distance2(world: &World, a: (usize, usize), b: (usize, usize)) -> f32 {
+    let mut dx = a.0.abs_diff(b.0);
+    let mut dy = a.1.abs_diff(b.1);
+    if world.topology == Topology::Torus {
+        dx = dx.min(world.width - dx);
+        dy = dy.min(world.height - dy);
+    }
+    (dx * dx + dy * dy) as f32
+}
+
+/// Searches outward in growing squares for a cell that can be claimed and isn't
+/// `taken`.
+fn nearest_free(world: &World, x: usize, y: usize, taken: &[bool]) -> Option<(usize, usize)> {
+    let max_radius = world.width.max(world.height) as isize;
+    (0..=max_radius).find_map(|r| {
+        (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter(|(dx, dy)| dx.abs() == r || dy.abs() == r)
+            .map(|(dx, dy)| (x as isize + dx, y as isize + dy))
+            .find(|&(x, y)| {
+                x >= 0
+                    && y >= 0
+                    && (x as usize) < world.width
+                    && (y as usize) < world.height
+                    && world.terrain[y as usize * world.width + x as usize].claimable()
+                    && !taken[y as usize * world.width + x as usize]
+            })
+            .map(|(x, y)| (x as usize, y as usize))
+    })
+}
@@ -0,0 +1,35 @@
+use crate::world::World;
+
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct EmpireStats {
+    pub id: u16,
+    pub cells: usize,
+    pub troops: u64,
+    /// see [`crate::tech`]
+    pub research: u64,
+    pub tier: usize,
+}
+
+impl World {
+    /// Cells and troops held by each empire and how far it has researched, in
+    /// the same order as `self.empires`.
+    pub fn stats(&self) -> Vec<EmpireStats> {
+        let mut stats = self
+            .empires
+            .iter()
+            .map(|empire| EmpireStats {
+                id: empire.id,
+                research: empire.tech.research,
+                tier: empire.tech.tier,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+        for cell in self.cells.iter() {
+            if let Some(stats) = stats.get_mut((cell.owner as usize).wrapping_sub(1)) {
+                stats.cells += 1;
+                stats.troops += cell.troops as u64;
+            }
+        }
+        stats
+    }
+}
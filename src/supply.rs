@@ -0,0 +1,112 @@
+//! Which parts of each empire's territory hang together. An empire's biggest
+//! connected piece of territory is its main body, and any others are pockets
+//! cut off from it. Troops never flow into pockets, and with
+//! `params.attrition` they waste away there too.
+use std::collections::VecDeque;
+
+use crate::world::{Cell, Troops, World};
+
+/// The label of unowned cells.
+pub const NO_COMPONENT: u32 = u32::MAX;
+
+/// A connected piece of one empire's territory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Component {
+    pub owner: u16,
+    pub cells: usize,
+    /// whether this is its empire's main body
+    pub main: bool,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct Components {
+    /// the index in `components` of each cell's component
+    pub labels: Vec<u32>,
+    pub components: Vec<Component>,
+}
+impl Components {
+    /// Whether cell `i` is owned, but cut off from its empire's main body.
+    pub fn cut_off(&self, i: usize) -> bool {
+        self.components
+            .get(self.labels[i] as usize)
+            .is_some_and(|component| !component.main)
+    }
+}
+
+impl World {
+    /// Splits every empire's territory into pieces, going from cell to cell
+    /// the same way troops do. Pieces are numbered in the order of their first
+    /// cell, and the first of an empire's biggest pieces is its main body.
+    pub fn components(&self) -> Components {
+        let mut labels = vec![NO_COMPONENT; self.cells.len()];
+        let mut components = vec![];
+        let mut queue = VecDeque::new();
+        for start in 0..self.cells.len() {
+            let owner = self.cells.get(start).owner;
+            if owner == 0 || labels[start] != NO_COMPONENT {
+                continue;
+            }
+
+            let label = components.len() as u32;
+            let mut cells = 0;
+            labels[start] = label;
+            queue.push_back(start);
+            while let Some(i) = queue.pop_front() {
+                cells += 1;
+                for j in self.neighbor_indices(i).into_iter().flatten() {
+                    if labels[j] == NO_COMPONENT && self.cells.get(j).owner == owner {
+                        labels[j] = label;
+                        queue.push_back(j);
+                    }
+                }
+            }
+            components.push(Component {
+                owner,
+                cells,
+                main: false,
+            });
+        }
+
+        for owner in 1..=self.empires.len() as u16 {
+            let mut biggest: Option<&mut Component> = None;
+            for component in components.iter_mut().filter(|c| c.owner == owner) {
+                if biggest.as_ref().is_none_or(|b| component.cells > b.cells) {
+                    biggest = Some(component);
+                }
+            }
+            if let Some(component) = biggest {
+                component.main = true;
+            }
+        }
+        Components { labels, components }
+    }
+
+    /// Takes `params.attrition` of the troops of every cut off cell.
+    pub(crate) fn attrition(&mut self) {
+        let attrition = self.params.attrition.min(1.0);
+        if attrition <= 0.0 {
+            return;
+        }
+        let components = self.components();
+        for i in 0..self.cells.len() {
+            if components.cut_off(i) {
+                let cell = self.cells.get(i);
+                let troops = (cell.troops as f32 * (1.0 - attrition)) as Troops;
+                self.cells.set(i, Cell { troops, ..cell });
+            }
+        }
+    }
+
+    /// Hatches the cut off cells of a frame drawn by [`World::draw`].
+    pub fn draw_cut_off(&self, frame: &mut [u8]) {
+        let components = self.components();
+        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            if components.cut_off(i) && (x + y) % 4 < 2 {
+                for channel in &mut pixel[..3] {
+                    *channel = (*channel / 2).saturating_add(128);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! Runs many headless matches in parallel over a grid of parameters, spawn
+//! strategies and seeds, and sums up how they went.
+//!
+//! A sweep file is a scenario file (see [`crate::scenario`]) with a `[sweep]`
+//! table. Every combination of `params` and `spawns` is played `runs` times,
+//! with seeds `0..runs`:
+//!
+//! ```toml
+//! width = 128
+//! height = 128
+//!
+//! [[empires]]
+//! color = [255, 0, 0]
+//! [[empires]]
+//! color = [0, 0, 255]
+//!
+//! [sweep]
+//! runs = 16
+//! max_ticks = 20000
+//! params = [{ decay = 0.9 }, { decay = 0.95 }]
+//! spawns = [{ strategy = "random" }, { strategy = "corners", fair = true }]
+//! ```
+//!
+//! Each of `params` only needs the keys it changes, the rest are the
+//! scenario's own `[params]`. Without `params` or `spawns`, the scenario's own
+//! are used.
+use std::{fmt, fs, io, path::Path};
+
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    scenario::{check_params, Scenario, ScenarioError},
+    spawn::Spawn,
+    world::{SimParams, World},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sweep {
+    pub scenario: Scenario,
+    pub grid: Grid,
+}
+
+/// The `[sweep]` table.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Grid {
+    pub runs: u64,
+    /// matches without a winner by then count as draws
+    pub max_ticks: usize,
+    /// how often to record each empire's share of the territory
+    pub sample_every: usize,
+    /// `[params]` keys to lay over the scenario's, one config each
+    pub params: Vec<toml::value::Table>,
+    pub spawns: Vec<Spawn>,
+}
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            runs: 8,
+            max_ticks: 10_000,
+            sample_every: 100,
+            params: vec![],
+            spawns: vec![],
+        }
+    }
+}
+
+/// One match in the grid, with indices into the sweep's configs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub config: usize,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResult {
+    pub setup: Match,
+    /// rebels (see [`crate::rebellion`]) win for the empire they broke away
+    /// from
+    pub winner: Option<u16>,
+    pub ticks: usize,
+    /// `World::checksum` at the end, for comparing runs across machines
+    pub checksum: u64,
+    /// the tick and each empire's share of the claimable cells then, its
+    /// rebels' included, every `sample_every` ticks and at the end
+    pub territory: Vec<(usize, Vec<f32>)>,
+}
+
+/// One combination of params and spawn strategy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub params: SimParams,
+    pub spawn: Option<Spawn>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigSummary {
+    pub config: Config,
+    pub runs: usize,
+    /// per empire
+    pub wins: Vec<usize>,
+    pub draws: usize,
+    /// over the matches that had a winner
+    pub mean_ticks: Option<f64>,
+    /// average of `MatchResult::territory` at each of `ticks`. Matches that
+    /// ended early keep their final shares.
+    pub territory: Vec<Vec<f32>>,
+    /// every `sample_every` ticks, and when the last match ended
+    pub ticks: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub empires: Vec<String>,
+    pub sample_every: usize,
+    pub summaries: Vec<ConfigSummary>,
+    pub results: Vec<MatchResult>,
+}
+
+impl Sweep {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ScenarioError> {
+        // The rest of the file is the scenario, which doesn't know `[sweep]`.
+        let mut table: toml::value::Table = toml::from_str(source)?;
+        let sweep: Grid = match table.remove("sweep") {
+            Some(sweep) => sweep.try_into()?,
+            None => Grid::default(),
+        };
+        if sweep.sample_every == 0 {
+            return Err(ScenarioError::Invalid(
+                "sweep.sample_every must be at least 1".into(),
+            ));
+        }
+        let sweep = Self {
+            scenario: toml::Value::Table(table).try_into()?,
+            grid: sweep,
+        };
+        sweep.configs()?;
+        Ok(sweep)
+    }
+
+    /// Every combination of `grid.params` and `grid.spawns`, or an error if
+    /// any of the params are invalid.
+    pub fn configs(&self) -> Result<Vec<Config>, ScenarioError> {
+        let params = if self.grid.params.is_empty() {
+            vec![self.scenario.params.clone()]
+        } else {
+            self.grid
+                .params
+                .iter()
+                .map(|changes| self.params(changes))
+                .collect::<Result<_, _>>()?
+        };
+        for params in &params {
+            check_params(params).map_err(ScenarioError::Invalid)?;
+        }
+        let spawns = if self.grid.spawns.is_empty() {
+            vec![self.scenario.spawn]
+        } else {
+            self.grid.spawns.iter().copied().map(Some).collect()
+        };
+
+        Ok(params
+            .iter()
+            .flat_map(|params| {
+                spawns.iter().map(|&spawn| Config {
+                    params: params.clone(),
+                    spawn,
+                })
+            })
+            .collect())
+    }
+
+    /// The scenario's params with `changes` laid over them.
+    fn params(&self, changes: &toml::value::Table) -> Result<SimParams, ScenarioError> {
+        let mut params = toml::Value::try_from(&self.scenario.params).map_err(|e| {
+            ScenarioError::Invalid(format!("couldn't merge the sweep's params: {}", e))
+        })?;
+        merge(&mut params, changes);
+        Ok(params.try_into()?)
+    }
+
+    pub fn matches(&self) -> Vec<Match> {
+        let configs = self.grid.params.len().max(1) * self.grid.spawns.len().max(1);
+        (0..configs)
+            .flat_map(|config| (0..self.grid.runs).map(move |seed| Match { config, seed }))
+            .collect()
+    }
+
+    /// Plays every match, in parallel.
+    pub fn run(&self) -> Result<Report, ScenarioError> {
+        // The empires don't need their own starts if every config spawns them.
+        let mut scenario = self.scenario.clone();
+        if let Some(&spawn) = self.grid.spawns.first() {
+            scenario.spawn = Some(spawn);
+        }
+        let base = scenario.build()?;
+        let configs = self.configs()?;
+
+        let results = self
+            .matches()
+            .par_iter()
+            .map(|&setup| self.play(&base, &configs[setup.config], setup))
+            .collect::<Vec<_>>();
+
+        let summaries = configs
+            .into_iter()
+            .enumerate()
+            .map(|(i, config)| {
+                let results = results
+                    .iter()
+                    .filter(|result| result.setup.config == i)
+                    .collect::<Vec<_>>();
+                summarize(config, &results, base.empires.len(), self.grid.sample_every)
+            })
+            .collect();
+
+        Ok(Report {
+            empires: base
+                .empires
+                .iter()
+                .map(|empire| empire.name.clone())
+                .collect(),
+            sample_every: self.grid.sample_every,
+            summaries,
+            results,
+        })
+    }
+
+    fn play(&self, base: &World, config: &Config, setup: Match) -> MatchResult {
+        let mut world = base.clone();
+        world.seed = setup.seed;
+        world.params = config.params.clone();
+        if let Some(spawn) = config.spawn {
+            spawn.apply(&mut world, &mut StdRng::seed_from_u64(setup.seed));
+        }
+
+        let claimable = world
+            .terrain
+            .iter()
+            .filter(|terrain| terrain.claimable())
+            .count()
+            .max(1) as f32;
+        let empires = world.empires.len();
+        let shares = |world: &World| {
+            let mut shares = vec![0.0; empires];
+            for stats in world.stats() {
+                shares[founder(world, stats.id) as usize - 1] += stats.cells as f32 / claimable;
+            }
+            shares
+        };
+
+        let mut territory = vec![(world.tick, shares(&world))];
+        let mut winner = world.winner();
+        while winner.is_none() && world.tick < self.grid.max_ticks {
+            let ticks = self.grid.sample_every.min(self.grid.max_ticks - world.tick);
+            for _ in 0..ticks {
+                world.update();
+                winner = world.winner();
+                if winner.is_some() {
+                    break;
+                }
+            }
+            territory.push((world.tick, shares(&world)));
+        }
+
+        MatchResult {
+            setup,
+            winner: winner.map(|winner| founder(&world, winner)),
+            ticks: world.tick,
+            checksum: world.checksum(),
+            territory,
+        }
+    }
+}
+
+/// Sets the keys of `changes` in `table`, going into the tables they share.
+/// Tables with a `type` replace what was there, since their other keys depend
+/// on it.
+fn merge(table: &mut toml::Value, changes: &toml::value::Table) {
+    let toml::Value::Table(table) = table else {
+        return;
+    };
+    for (key, change) in changes {
+        match (table.get_mut(key), change) {
+            (Some(value @ toml::Value::Table(_)), toml::Value::Table(changes))
+                if !changes.contains_key("type") =>
+            {
+                merge(value, changes)
+            }
+            _ => {
+                table.insert(key.clone(), change.clone());
+            }
+        }
+    }
+}
+
+/// The empire `id` broke away from, through any number of rebellions, or `id`
+/// itself if it was there from the start.
+fn founder(world: &World, mut id: u16) -> u16 {
+    while let Some(rebellion) = world
+        .rebellions
+        .iter()
+        .find(|rebellion| rebellion.rebels == id)
+    {
+        id = rebellion.empire;
+    }
+    id
+}
+
+fn summarize(
+    config: Config,
+    results: &[&MatchResult],
+    empires: usize,
+    sample_every: usize,
+) -> ConfigSummary {
+    let mut wins = vec![0; empires];
+    for winner in results.iter().filter_map(|result| result.winner) {
+        wins[winner as usize - 1] += 1;
+    }
+    let finished = results
+        .iter()
+        .filter(|result| result.winner.is_some())
+        .map(|result| result.ticks)
+        .collect::<Vec<_>>();
+
+    let end = results
+        .iter()
+        .filter_map(|result| result.territory.last())
+        .map(|&(tick, _)| tick)
+        .max();
+    let ticks = end.map_or(vec![], |end| {
+        (0..end).step_by(sample_every).chain([end]).collect()
+    });
+    let territory = ticks
+        .iter()
+        .map(|&tick| {
+            let mut mean = vec![0.0; empires];
+            for result in results {
+                // The last sample by then, which for a match that's over is
+                // how it ended.
+                let Some((_, shares)) = result.territory.iter().rev().find(|(t, _)| *t <= tick)
+                else {
+                    continue;
+                };
+                for (mean, share) in mean.iter_mut().zip(shares) {
+                    *mean += share / results.len() as f32;
+                }
+            }
+            mean
+        })
+        .collect();
+
+    ConfigSummary {
+        config,
+        runs: results.len(),
+        draws: results.len() - finished.len(),
+        mean_ticks: (!finished.is_empty())
+            .then(|| finished.iter().sum::<usize>() as f64 / finished.len() as f64),
+        wins,
+        territory,
+        ticks,
+    }
+}
+
+impl Report {
+    /// The averaged territory curves as CSV, one row per config and sample.
+    pub fn write_curves(&self, mut out: impl io::Write) -> io::Result<()> {
+        write!(out, "config,tick")?;
+        for name in &self.empires {
+            write!(out, ",{}", name.replace(',', " "))?;
+        }
+        writeln!(out)?;
+
+        for (i, summary) in self.summaries.iter().enumerate() {
+            for (tick, shares) in summary.ticks.iter().zip(&summary.territory) {
+                write!(out, "{},{}", i, tick)?;
+                for share in shares {
+                    write!(out, ",{:.4}", share)?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, summary) in self.summaries.iter().enumerate() {
+            let Config { params, spawn } = &summary.config;
+            write!(
+                f,
+                "config {}: decay {}, takeover {}..{}, {} combat",
+                i,
+                params.decay,
+                params.takeover_min,
+                params.takeover_max,
+                params.combat.name()
+            )?;
+            match spawn {
+                Some(spawn) => writeln!(
+                    f,
+                    ", {} spawn{}",
+                    spawn.strategy.name(),
+                    if spawn.fair { " (fair)" } else { "" }
+                )?,
+                None => writeln!(f, ", scenario starts")?,
+            }
+
+            write!(f, "  {} runs, {} draws", summary.runs, summary.draws)?;
+            match summary.mean_ticks {
+                Some(ticks) => writeln!(f, ", {:.0} ticks on average", ticks)?,
+                None => writeln!(f)?,
+            }
+
+            let last = summary.territory.last();
+            for (n, name) in self.empires.iter().enumerate() {
+                writeln!(
+                    f,
+                    "  {:<16} {:>5.1}% wins, {:>5.1}% territory at the end",
+                    name,
+                    100.0 * summary.wins[n] as f32 / summary.runs.max(1) as f32,
+                    100.0 * last.map_or(0.0, |shares| shares[n]),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
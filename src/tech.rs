@@ -0,0 +1,122 @@
+//! Empires getting better at war as a match goes on. Each tick, every empire
+//! gains `params.tech.per_cell` research points for each cell it holds. Once
+//! its research reaches the next tier's, it unlocks that tier for good, and the
+//! tier's traits stack onto its own (see [`crate::traits`]) from then on. An
+//! empire's `traits` stay what it started with; [`World::empire_traits`] is what
+//! it plays with.
+//!
+//! ```toml
+//! [params.tech]
+//! per_cell = 1
+//! tiers = [
+//!     { research = 1_000_000, traits = { growth = 1.01 } },
+//!     { research = 5_000_000, traits = { attack = 1.1, defense = 1.1 } },
+//! ]
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    traits::Traits,
+    world::{Empire, World},
+};
+
+/// How empires research, part of `SimParams`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TechParams {
+    /// research points a cell earns its empire each tick, 0 for no research
+    pub per_cell: u64,
+    /// unlocked in order, each at a higher `research` than the last
+    pub tiers: Vec<Tier>,
+}
+impl Default for TechParams {
+    fn default() -> Self {
+        Self {
+            per_cell: 0,
+            tiers: vec![
+                Tier {
+                    research: 1_000_000,
+                    traits: Traits {
+                        growth: 1.01,
+                        ..Default::default()
+                    },
+                },
+                Tier {
+                    research: 5_000_000,
+                    traits: Traits {
+                        attack: 1.1,
+                        defense: 1.1,
+                        ..Default::default()
+                    },
+                },
+                Tier {
+                    research: 20_000_000,
+                    traits: Traits {
+                        attack: 1.1,
+                        decay: 0.8,
+                        ..Default::default()
+                    },
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tier {
+    /// total research points it takes
+    pub research: u64,
+    /// stacked onto the empire's traits once unlocked
+    #[serde(default)]
+    pub traits: Traits,
+}
+
+/// How far an empire has researched.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tech {
+    /// points earned so far
+    pub research: u64,
+    /// how many of `params.tech.tiers` it has unlocked
+    pub tier: usize,
+}
+
+impl World {
+    /// `empire`'s own traits with those of every tier it has unlocked.
+    pub fn empire_traits(&self, empire: &Empire) -> Traits {
+        self.params
+            .tech
+            .tiers
+            .iter()
+            .take(empire.tech.tier)
+            .fold(empire.traits, |traits, tier| traits.stack(&tier.traits))
+    }
+
+    /// Adds this tick's research, and unlocks the tiers it's enough for.
+    pub(crate) fn research(&mut self) {
+        let per_cell = self.params.tech.per_cell;
+        if per_cell == 0 {
+            return;
+        }
+        let stats = self.stats();
+        let tiers = &self.params.tech.tiers;
+        for (empire, stats) in self.empires.iter_mut().zip(stats) {
+            let tech = &mut empire.tech;
+            tech.research = tech
+                .research
+                .saturating_add((stats.cells as u64).saturating_mul(per_cell));
+            while let Some(tier) = tiers.get(tech.tier) {
+                if tech.research < tier.research {
+                    break;
+                }
+                tech.tier += 1;
+                log::info!(
+                    "{} reached tier {} at tick {}",
+                    empire.name,
+                    tech.tier,
+                    self.tick
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Terrain {
+    #[default]
+    Plains,
+    /// can never be claimed
+    Water,
+    Hills,
+    Mountains,
+    /// claimable, but slows down attackers crossing it
+    River,
+}
+impl Terrain {
+    /// Multiplier applied to a cell's troops when an enemy neighbor tries to take it.
+    pub fn defense(self) -> f32 {
+        match self {
+            Terrain::Plains | Terrain::Water => 1.0,
+            Terrain::River => 1.25,
+            Terrain::Hills => 1.5,
+            Terrain::Mountains => 2.0,
+        }
+    }
+
+    pub fn claimable(self) -> bool {
+        self != Terrain::Water
+    }
+
+    /// Color used for unclaimed cells.
+    pub fn color(self) -> [u8; 4] {
+        match self {
+            Terrain::Plains => [0x00, 0x00, 0x00, 0xff],
+            Terrain::Water => [0x0a, 0x1a, 0x3a, 0xff],
+            Terrain::Hills => [0x22, 0x24, 0x14, 0xff],
+            Terrain::Mountains => [0x38, 0x38, 0x38, 0xff],
+            Terrain::River => [0x14, 0x30, 0x5a, 0xff],
+        }
+    }
+
+    /// `.` plains, `~` water, `n` hills, `^` mountains, `=` river
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '.' => Some(Terrain::Plains),
+            '~' => Some(Terrain::Water),
+            'n' => Some(Terrain::Hills),
+            '^' => Some(Terrain::Mountains),
+            '=' => Some(Terrain::River),
+            _ => None,
+        }
+    }
+}
+
+/// Where a world's terrain comes from.
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TerrainSource {
+    /// every cell is the same
+    #[default]
+    Flat,
+    /// A hand-drawn map, one string per row (see [`Terrain::from_char`]).
+    /// It is stretched to fit the world, so it doesn't need to match its size.
+    Ascii { rows: Vec<String> },
+    /// see [`TerrainGenerator`]
+    Noise(TerrainGenerator),
+}
+impl TerrainSource {
+    pub fn generate(&self, width: usize, height: usize) -> Result<Vec<Terrain>, String> {
+        match self {
+            TerrainSource::Flat => Ok(vec![Terrain::Plains; width * height]),
+            TerrainSource::Ascii { rows } => {
+                let map = rows
+                    .iter()
+                    .enumerate()
+                    .map(|(y, row)| {
+                        row.chars()
+                            .map(|c| {
+                                Terrain::from_char(c).ok_or_else(|| {
+                                    format!("unknown terrain character {:?} on row {}", c, y + 1)
+                                })
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let map_height = map.len();
+                let map_width = map.first().map_or(0, |row| row.len());
+                if map_width == 0 || map.iter().any(|row| row.len() != map_width) {
+                    return Err("ascii terrain rows must be non-empty and the same length".into());
+                }
+
+                Ok((0..width * height)
+                    .map(|i| {
+                        let x = i % width * map_width / width;
+                        let y = i / width * map_height / height;
+                        map[y][x]
+                    })
+                    .collect())
+            }
+            TerrainSource::Noise(generator) => Ok(generator.generate(width, height)),
+        }
+    }
+}
+
+/// Seeded fractal value noise, cut into terrain types by elevation, with rivers
+/// running downhill from the high ground.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerrainGenerator {
+    pub seed: u64,
+    /// size of the largest features, in cells
+    pub scale: f32,
+    pub octaves: u32,
+    /// How much each octave contributes compared to the one before it.
+    pub persistence: f32,
+    /// Elevations (0..1) at which each type starts. Anything lower than `plains` is water.
+    pub plains: f32,
+    pub hills: f32,
+    pub mountains: f32,
+    pub rivers: usize,
+}
+impl Default for TerrainGenerator {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            scale: 96.0,
+            octaves: 4,
+            persistence: 0.5,
+            plains: 0.3,
+            hills: 0.65,
+            mountains: 0.8,
+            rivers: 6,
+        }
+    }
+}
+impl TerrainGenerator {
+    /// The noise tiles across the edges, so it also fits worlds that wrap around.
+    pub fn generate(&self, width: usize, height: usize) -> Vec<Terrain> {
+        if width == 0 || height == 0 {
+            return vec![];
+        }
+        let elevation = self.elevation(width, height);
+
+        let mut terrain = elevation
+            .iter()
+            .map(|&e| {
+                if e < self.plains {
+                    Terrain::Water
+                } else if e < self.hills {
+                    Terrain::Plains
+                } else if e < self.mountains {
+                    Terrain::Hills
+                } else {
+                    Terrain::Mountains
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let sources = (0..elevation.len())
+            .filter(|&i| elevation[i] >= self.hills)
+            .collect::<Vec<_>>();
+        for _ in 0..self.rivers {
+            if sources.is_empty() {
+                break;
+            }
+            let mut i = sources[rng.gen_range(0..sources.len())];
+
+            // Flow downhill until reaching water, leaving a small lake
+            // where it gets stuck in a dip.
+            for _ in 0..width + height {
+                if terrain[i] == Terrain::Water {
+                    break;
+                }
+                terrain[i] = Terrain::River;
+
+                let (x, y) = ((i % width) as isize, (i / width) as isize);
+                let next = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .into_iter()
+                    .map(|(dx, dy)| {
+                        (y + dy).rem_euclid(height as isize) as usize * width
+                            + (x + dx).rem_euclid(width as isize) as usize
+                    })
+                    .filter(|&n| terrain[n] != Terrain::River)
+                    .min_by(|&a, &b| elevation[a].total_cmp(&elevation[b]));
+                match next {
+                    Some(next) if elevation[next] <= elevation[i] => i = next,
+                    _ => {
+                        terrain[i] = Terrain::Water;
+                        break;
+                    }
+                }
+            }
+        }
+
+        terrain
+    }
+
+    /// Fractal value noise, normalized to 0..1.
+    pub(crate) fn elevation(&self, width: usize, height: usize) -> Vec<f32> {
+        let mut elevation = vec![0.0f32; width * height];
+        let mut amplitude = 1.0;
+        let mut feature = self.scale.max(1.0);
+
+        for octave in 0..self.octaves.max(1) {
+            // A whole number of lattice points across each axis, so that it tiles.
+            let lattice_w = ((width as f32 / feature).round() as usize).max(1);
+            let lattice_h = ((height as f32 / feature).round() as usize).max(1);
+            let seed = self.seed.wrapping_add(octave as u64);
+
+            for (i, e) in elevation.iter_mut().enumerate() {
+                let u = (i % width) as f32 / width as f32 * lattice_w as f32;
+                let v = (i / width) as f32 / height as f32 * lattice_h as f32;
+                let (x0, y0) = (u as usize, v as usize);
+                let (x1, y1) = ((x0 + 1) % lattice_w, (y0 + 1) % lattice_h);
+                let (tx, ty) = (smoothstep(u.fract()), smoothstep(v.fract()));
+
+                let top = lerp(lattice(seed, x0, y0), lattice(seed, x1, y0), tx);
+                let bottom = lerp(lattice(seed, x0, y1), lattice(seed, x1, y1), tx);
+                *e += lerp(top, bottom, ty) * amplitude;
+            }
+
+            amplitude *= self.persistence;
+            feature /= 2.0;
+        }
+
+        let (min, max) = elevation
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(min, max), &e| {
+                (min.min(e), max.max(e))
+            });
+        let range = (max - min).max(f32::EPSILON);
+        for e in &mut elevation {
+            *e = (*e - min) / range;
+        }
+        elevation
+    }
+}
+
+/// Pseudorandom value in 0..1 for a lattice point.
+fn lattice(seed: u64, x: usize, y: usize) -> f32 {
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
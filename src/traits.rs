@@ -0,0 +1,186 @@
+//! What sets empires apart. Each [`Empire`](crate::world::Empire) has
+//! [`Traits`] that scale the rules for it, and a [`Faction`] is a ready-made
+//! set of them.
+//!
+//! ```toml
+//! [[empires]]
+//! color = [255, 0, 0]
+//! faction = "horde"
+//!
+//! [[empires]]
+//! color = [0, 0, 255]
+//! traits = { defense = 1.2, affinities = { mountains = 1.5 } }
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::terrain::Terrain;
+
+/// Multipliers on the rules for one empire, where 1 changes nothing.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Traits {
+    /// on the troops it attacks other empires and the wild with
+    pub attack: f32,
+    /// on the terrain's defense of its cells
+    pub defense: f32,
+    /// on the troops its cells lose to decay each tick
+    pub decay: f32,
+    /// on the troops a cell gets when it takes over or reinforces another
+    pub growth: f32,
+    pub affinities: Affinities,
+}
+impl Traits {
+    /// Changes nothing, also what unclaimed cells have.
+    pub const NEUTRAL: Traits = Traits {
+        attack: 1.0,
+        defense: 1.0,
+        decay: 1.0,
+        growth: 1.0,
+        affinities: Affinities {
+            plains: 1.0,
+            hills: 1.0,
+            mountains: 1.0,
+            river: 1.0,
+        },
+    };
+
+    /// Both these and `other` at once.
+    pub fn stack(&self, other: &Traits) -> Traits {
+        let (a, b) = (&self.affinities, &other.affinities);
+        Traits {
+            attack: self.attack * other.attack,
+            defense: self.defense * other.defense,
+            decay: self.decay * other.decay,
+            growth: self.growth * other.growth,
+            affinities: Affinities {
+                plains: a.plains * b.plains,
+                hills: a.hills * b.hills,
+                mountains: a.mountains * b.mountains,
+                river: a.river * b.river,
+            },
+        }
+    }
+
+    /// The defense of a cell on `terrain` held with these traits.
+    pub fn defense(&self, terrain: Terrain) -> f32 {
+        terrain.defense() * self.defense * self.affinities.get(terrain)
+    }
+}
+impl Default for Traits {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+/// Further multipliers on the defense of cells on each kind of terrain.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Affinities {
+    pub plains: f32,
+    pub hills: f32,
+    pub mountains: f32,
+    pub river: f32,
+}
+impl Default for Affinities {
+    fn default() -> Self {
+        Traits::NEUTRAL.affinities
+    }
+}
+impl Affinities {
+    /// Water can't be held, so it has none.
+    pub fn get(&self, terrain: Terrain) -> f32 {
+        match terrain {
+            Terrain::Plains => self.plains,
+            Terrain::Hills => self.hills,
+            Terrain::Mountains => self.mountains,
+            Terrain::River => self.river,
+            Terrain::Water => 1.0,
+        }
+    }
+}
+
+/// Preset traits to pick from when adding an empire.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Faction {
+    #[default]
+    Balanced,
+    /// hits hard and spreads fast, but holds nothing for long
+    Horde,
+    /// slow, but hard to dislodge
+    Fortress,
+    /// at home in the hills and mountains, lost on open ground
+    Highlanders,
+    /// lives along the rivers and grows from them
+    RiverFolk,
+    /// roams the plains and travels light
+    Nomads,
+}
+impl Faction {
+    pub const ALL: [Faction; 6] = [
+        Faction::Balanced,
+        Faction::Horde,
+        Faction::Fortress,
+        Faction::Highlanders,
+        Faction::RiverFolk,
+        Faction::Nomads,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Faction::Balanced => "Balanced",
+            Faction::Horde => "Horde",
+            Faction::Fortress => "Fortress",
+            Faction::Highlanders => "Highlanders",
+            Faction::RiverFolk => "River folk",
+            Faction::Nomads => "Nomads",
+        }
+    }
+
+    pub fn traits(self) -> Traits {
+        let affinities = Affinities::default();
+        match self {
+            Faction::Balanced => Traits::default(),
+            Faction::Horde => Traits {
+                attack: 1.25,
+                defense: 0.8,
+                decay: 1.5,
+                growth: 1.02,
+                affinities,
+            },
+            Faction::Fortress => Traits {
+                attack: 0.85,
+                defense: 1.4,
+                decay: 0.8,
+                ..Default::default()
+            },
+            Faction::Highlanders => Traits {
+                affinities: Affinities {
+                    plains: 0.85,
+                    hills: 1.4,
+                    mountains: 1.4,
+                    ..affinities
+                },
+                ..Default::default()
+            },
+            Faction::RiverFolk => Traits {
+                growth: 1.01,
+                affinities: Affinities {
+                    river: 1.6,
+                    ..affinities
+                },
+                ..Default::default()
+            },
+            Faction::Nomads => Traits {
+                attack: 1.1,
+                defense: 0.9,
+                decay: 0.7,
+                affinities: Affinities {
+                    plains: 1.2,
+                    ..affinities
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
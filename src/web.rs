@@ -0,0 +1,91 @@
+//! JavaScript bindings for running the simulation in a browser, see `web/`.
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    world::{Cell, Empire, Troops, World},
+};
+
+/// A [`World`], as seen from JavaScript.
+#[wasm_bindgen(js_name = World)]
+pub struct WebWorld {
+    world: World,
+}
+
+#[wasm_bindgen(js_class = World)]
+impl WebWorld {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            world: World::new(width, height),
+        }
+    }
+
+    /// Builds a world from the contents of a scenario file.
+    #[wasm_bindgen(js_name = fromScenario)]
+    pub fn from_scenario(toml: &str) -> Result<WebWorld, JsError> {
+        let world = Scenario::from_toml(toml)?.build()?;
+        Ok(Self { world })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.world.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.world.height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn tick(&self) -> usize {
+        self.world.tick
+    }
+
+    pub fn update(&mut self) {
+        self.world.update();
+    }
+
+    /// Draws into an RGBA `Uint8Array` of `width * height * 4` bytes.
+    pub fn draw(&self, frame: &mut [u8]) {
+        self.world.draw(frame);
+    }
+
+    /// Out of bounds cells and unknown owners are ignored.
+    #[wasm_bindgen(js_name = setCell)]
+    pub fn set_cell(&mut self, x: usize, y: usize, owner: u16, troops: Troops) {
+        if x < self.world.width
+            && y < self.world.height
+            && owner as usize <= self.world.empires.len()
+        {
+            self.world
+                .set(x as isize, y as isize, Cell { owner, troops });
+        }
+    }
+
+    /// Returns the new empire's id.
+    #[wasm_bindgen(js_name = addEmpire)]
+    pub fn add_empire(&mut self, name: String, r: u8, g: u8, b: u8) -> u16 {
+        let id = (self.world.empires.len() + 1) as u16;
+        self.world
+            .empires
+            .push(Empire::new(id, name, (r, g, b, 255)));
+        id
+    }
+
+    /// Clears the world and places every empire at random.
+    pub fn spawn(&mut self, fair: bool) {
+        Spawn {
+            strategy: SpawnStrategy::EvenlySpaced,
+            fair,
+        }
+        .apply(&mut self.world, &mut rand::thread_rng());
+    }
+
+    /// The winning empire's id, or 0 while nobody has won.
+    pub fn winner(&self) -> u16 {
+        self.world.winner().unwrap_or(0)
+    }
+}
@@ -1,86 +1,166 @@
-use rand::{seq::SliceRandom, Rng};
+#[cfg(feature = "scripting")]
+use std::sync::Arc;
+
 #[cfg(not(target_arch = "wasm32"))]
-use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use rayon::prelude::{IndexedParallelIterator, ParallelIterator, ParallelSliceMut};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cells::{Cells, CellsMut, Layout, Storage},
+    combat::Combat,
+    events::Scheduled,
+    fog::Visibility,
+    rebellion::{Rebellion, RebellionParams},
+    rng::CellRng,
+    tech::{Tech, TechParams},
+    terrain::Terrain,
+    traits::Traits,
+};
+
+/// Side length of the square tiles that are skipped while nothing happens in them.
+const TILE: usize = 32;
 
+/// Where each of a cell's neighbors is, in the order the rules take them.
+const NEIGHBORS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+/// The neighbor in the opposite direction of each of `NEIGHBORS`.
+const OPPOSITE: [usize; 8] = [1, 0, 3, 2, 7, 6, 5, 4];
+
+#[derive(Clone)]
 pub struct World {
-    pub cells: Vec<Cell>,
+    pub cells: Cells,
+    /// last tick's cells, reused as the output of the next one
+    back: Cells,
+    pub terrain: Vec<Terrain>,
+    /// troops each unclaimed cell holds, see [`crate::neutral`]
+    pub garrisons: Vec<Troops>,
     pub width: usize,
     pub height: usize,
+    pub topology: Topology,
+    pub params: SimParams,
     pub empires: Vec<Empire>,
+    pub victory: Vec<VictoryCondition>,
     pub tick: usize,
+    /// every rebellion so far, oldest first
+    pub rebellions: Vec<Rebellion>,
+    /// what each empire sees, in the same order as `empires`, see
+    /// [`crate::fog`]
+    pub visibility: Vec<Visibility>,
+    /// the same seed always plays out the same way
+    pub seed: u64,
+    /// replaces the built-in rules while set
+    #[cfg(feature = "scripting")]
+    pub script: Option<Arc<crate::script::ScriptRule>>,
+    /// Debug mode: runs every tick serially too, and compares it with the
+    /// parallel result.
+    pub check_parallel: bool,
+    /// the first tick at which the serial and parallel results differed
+    pub parallel_mismatch: Option<usize>,
+    active_tiles: Vec<bool>,
+    /// what the events on the tick being computed do to every empire
+    seasonal: Traits,
+    /// every empire's traits with its tiers' for the tick being computed, in
+    /// the same order as `empires`
+    effective: Vec<Traits>,
 }
 impl World {
     pub fn new(width: usize, height: usize) -> Self {
         Self {
-            cells: vec![Cell::default(); width * height],
+            cells: Cells::new(Layout::default(), width * height),
+            back: Cells::new(Layout::default(), width * height),
+            terrain: vec![Terrain::default(); width * height],
+            garrisons: vec![0; width * height],
             empires: vec![],
             width,
             height,
+            topology: Topology::default(),
+            params: SimParams::default(),
+            victory: vec![VictoryCondition::LastStanding],
             tick: 0,
+            rebellions: vec![],
+            visibility: vec![],
+            seed: rand::random(),
+            #[cfg(feature = "scripting")]
+            script: None,
+            check_parallel: false,
+            parallel_mismatch: None,
+            active_tiles: vec![],
+            seasonal: Traits::default(),
+            effective: vec![],
         }
     }
 
-    /// clears all cells
+    /// clears all cells, terrain and garrisons
     pub fn resize(&mut self, width: usize, height: usize) {
         self.width = width;
         self.height = height;
-        self.cells = vec![Cell::default(); width * height];
+        self.cells = Cells::new(self.cells.layout(), width * height);
+        self.terrain = vec![Terrain::default(); width * height];
+        self.garrisons = vec![0; width * height];
+    }
+
+    /// Switches how cells are stored, keeping them as they are.
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.cells = self.cells.to_layout(layout);
     }
 
     pub fn update(&mut self) {
-        self.cells = {
-            #[cfg(target_arch = "wasm32")]
-            {
-                self.cells.iter()
-            }
-            #[cfg(not(target_arch = "wasm32"))]
-            {
-                self.cells.par_iter()
-            }
+        self.step(true);
+    }
+
+    /// Like [`World::update`], but recomputes the static tiles too. The outcome is
+    /// the same, this is for checking that it stays that way.
+    pub fn update_full(&mut self) {
+        self.step(false);
+    }
+
+    fn step(&mut self, skip_static: bool) {
+        self.seasonal = self.seasonal_traits();
+        self.effective = self
+            .empires
+            .iter()
+            .map(|empire| self.empire_traits(empire))
+            .collect();
+
+        // Everything in the back buffer gets overwritten, so it is only replaced
+        // in case `cells` was replaced from outside.
+        let mut next = std::mem::replace(&mut self.back, Cells::new(Layout::default(), 0));
+        if next.len() != self.cells.len() || next.layout() != self.cells.layout() {
+            next = Cells::new(self.cells.layout(), self.cells.len());
         }
-        .copied()
-        .enumerate()
-        .map(|(i, cell)| {
-            let mut cell = cell;
-            let x = (i % self.width) as isize;
-            let y = (i / self.width) as isize;
-
-            let mut neighbors = [
-                self.get(x - 1, y),
-                self.get(x + 1, y),
-                self.get(x, y - 1),
-                self.get(x, y + 1),
-                self.get(x - 1, y - 1),
-                self.get(x + 1, y - 1),
-                self.get(x - 1, y + 1),
-                self.get(x + 1, y + 1),
-            ];
-            neighbors.shuffle(&mut rand::thread_rng());
-
-            cell.troops = (cell.troops as f32 * 0.95) as u16;
-
-            for neighbor in neighbors.iter().flatten() {
-                if neighbor.owner == cell.owner && neighbor.troops > cell.troops {
-                    cell.owner = neighbor.owner;
-                    cell.troops =
-                        (neighbor.troops as f32 * rand::thread_rng().gen_range(0.98..1.01)) as u16;
-                    break;
-                }
-                if neighbor.troops > cell.troops {
-                    cell.owner = neighbor.owner;
-                    cell.troops =
-                        (neighbor.troops as f32 * rand::thread_rng().gen_range(0.98..1.01)) as u16;
-                    break;
-                }
-            }
+        let mut active = std::mem::take(&mut self.active_tiles);
+        self.compute(&mut next, &mut active, skip_static, true);
 
-            if cell.owner == 0 {
-                cell.troops = 0;
+        if self.check_parallel {
+            let mut serial = Cells::new(self.cells.layout(), self.cells.len());
+            self.compute(&mut serial, &mut vec![], skip_static, false);
+            let checksum = |cells: &Cells| {
+                let mut checksum = Checksum::new();
+                checksum.add_cells(cells);
+                checksum.0
+            };
+            if self.parallel_mismatch.is_none() && checksum(&serial) != checksum(&next) {
+                log::error!(
+                    "the serial and parallel updates differ at tick {}",
+                    self.tick
+                );
+                self.parallel_mismatch = Some(self.tick);
             }
+        }
 
-            cell
-        })
-        .collect();
+        self.active_tiles = active;
+        self.back = std::mem::replace(&mut self.cells, next);
+        self.flow();
+        self.attrition();
+        self.plague();
 
         // self.cells = self
         //     .cells
@@ -156,60 +236,728 @@ impl World {
         //     })
         //     .collect();
         self.tick += 1;
+        self.research();
+        self.rebel();
+        self.update_visibility();
+    }
+
+    /// Computes the next tick's cells into `next`, on rayon's threads if `parallel`.
+    fn compute(&self, next: &mut Cells, active: &mut Vec<bool>, skip_static: bool, parallel: bool) {
+        if self.cells.is_empty() {
+            return;
+        }
+        let (cols, rows) = self.tiles();
+        active.resize(cols * rows, true);
+        if skip_static && !self.scripted() {
+            self.find_active_tiles(active, parallel);
+        } else {
+            active.fill(true);
+        }
+
+        let active = &*active;
+        next.for_each_band(self.width * TILE, parallel, |ty, band| {
+            self.update_band(ty, band, active)
+        });
+    }
+
+    /// Whether cells go through a script instead of the built-in rules.
+    pub(crate) fn scripted(&self) -> bool {
+        #[cfg(feature = "scripting")]
+        let scripted = self.script.is_some();
+        #[cfg(not(feature = "scripting"))]
+        let scripted = false;
+        scripted
+    }
+
+    /// Number of tile columns and rows.
+    fn tiles(&self) -> (usize, usize) {
+        (self.width.div_ceil(TILE), self.height.div_ceil(TILE))
+    }
+
+    /// A tile is active if any of its cells isn't settled, see [`World::settled`].
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn find_active_tiles(&self, active: &mut [bool], parallel: bool) {
+        let (cols, _) = self.tiles();
+        let w = self.width;
+
+        let find = |(ty, row): (usize, &mut [bool])| {
+            row.fill(false);
+            for y in ty * TILE..((ty + 1) * TILE).min(self.height) {
+                for (tx, active) in row.iter_mut().enumerate() {
+                    let span = y * w + tx * TILE..y * w + ((tx + 1) * TILE).min(w);
+                    *active = *active || span.into_iter().any(|i| !self.settled(i));
+                }
+            }
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if parallel {
+            active.par_chunks_mut(cols).enumerate().for_each(find);
+            return;
+        }
+        active.chunks_mut(cols).enumerate().for_each(find);
+    }
+
+    /// Whether cell `i` is guaranteed to stay the same as long as its neighbors
+    /// are settled too, whatever the dice say.
+    fn settled(&self, i: usize) -> bool {
+        let cell = self.cells.get(i);
+        if cell.owner == 0 && self.terrain[i].claimable() {
+            // Unclaimed cells never attack, so they only change by growing back.
+            return cell.troops >= self.garrison(i) || self.params.regrowth <= 0.0;
+        }
+        cell.troops == 0 && (cell.owner == 0 || self.terrain[i].claimable())
+    }
+
+    /// Computes the next state of the band of tile row `ty`. Tiles with no active
+    /// tiles around them are copied instead.
+    fn update_band(&self, ty: usize, mut band: CellsMut, active: &[bool]) {
+        let (cols, rows) = self.tiles();
+        let w = self.width;
+        let y0 = ty * TILE;
+
+        for tx in 0..cols {
+            let awake = (-1..=1isize).any(|dy| {
+                (-1..=1isize).any(|dx| {
+                    let (nx, ny) = (tx as isize + dx, ty as isize + dy);
+                    match self.topology {
+                        Topology::Torus => {
+                            active[ny.rem_euclid(rows as isize) as usize * cols
+                                + nx.rem_euclid(cols as isize) as usize]
+                        }
+                        Topology::Bounded => {
+                            nx >= 0
+                                && ny >= 0
+                                && (nx as usize) < cols
+                                && (ny as usize) < rows
+                                && active[ny as usize * cols + nx as usize]
+                        }
+                    }
+                })
+            });
+
+            let (x0, x1) = (tx * TILE, ((tx + 1) * TILE).min(w));
+            for y in y0..(y0 + TILE).min(self.height) {
+                let start = (y - y0) * w;
+                let out = band.slice(start + x0, start + x1);
+                if awake {
+                    self.update_span(y, x0, x1, out);
+                } else {
+                    let mut out = out;
+                    out.copy_from(&self.cells, y * w + x0);
+                }
+            }
+        }
+    }
+
+    /// Computes the next state of cells `x0..x1` of row `y` into `out`. Cells away
+    /// from the edges index their neighbors directly, only the edges go through `get`.
+    fn update_span(&self, y: usize, x0: usize, x1: usize, mut out: CellsMut) {
+        let w = self.width;
+        let edge = |x: usize| self.neighbors(y * w + x);
+
+        // Range of x that doesn't touch an edge. Scripts and battles with losses,
+        // which look further than the neighbors, get every cell through `get`.
+        let (lo, hi) = if y == 0
+            || y + 1 >= self.height
+            || w < 3
+            || self.scripted()
+            || self.params.combat.has_losses()
+        {
+            (x1, x1)
+        } else {
+            (x0.max(1).min(x1), x1.min(w - 1).max(x0))
+        };
+
+        for x in (x0..lo).chain(hi.max(lo)..x1) {
+            out.set(x - x0, self.next_cell(y * w + x, edge(x)));
+        }
+        if lo >= hi {
+            return;
+        }
+
+        match (&self.cells.storage, out.slice(lo - x0, hi - x0)) {
+            (
+                Storage::Soa { owners, troops },
+                CellsMut::Soa {
+                    owners: o,
+                    troops: t,
+                },
+            ) => self.update_planes(y * w + lo, owners, troops, o, t),
+            (Storage::Aos(c), mut out) => {
+                for x in lo..hi {
+                    let i = y * w + x;
+                    out.set(
+                        x - lo,
+                        self.next_cell(
+                            i,
+                            [
+                                Some(c[i - 1]),
+                                Some(c[i + 1]),
+                                Some(c[i - w]),
+                                Some(c[i + w]),
+                                Some(c[i - w - 1]),
+                                Some(c[i - w + 1]),
+                                Some(c[i + w - 1]),
+                                Some(c[i + w + 1]),
+                            ],
+                        ),
+                    );
+                }
+            }
+            _ => unreachable!("the back buffer has the same layout"),
+        }
+    }
+
+    /// Same as calling `next_cell` on each of the (at most `TILE`) cells from
+    /// `start`, which must not be on an edge, but looks up the traits of the
+    /// run and its neighbors first, so that the comparisons with the neighbors
+    /// are plain loops over arrays.
+    fn update_planes(
+        &self,
+        start: usize,
+        owners: &[u16],
+        troops: &[Troops],
+        owners_out: &mut [u16],
+        troops_out: &mut [Troops],
+    ) {
+        let n = owners_out.len();
+        let w = self.width as isize;
+        let offsets = NEIGHBORS.map(|(dx, dy)| dy * w + dx);
+
+        let mut decayed = [0 as Troops; TILE];
+        for (k, decayed) in decayed.iter_mut().enumerate().take(n) {
+            let (owner, troops) = (owners[start + k], troops[start + k]);
+            *decayed = self.rest(start + k, Cell { owner, troops });
+        }
+
+        // What a neighbor needs to beat, depending on whether it's friendly.
+        let mut friendly = [0f32; TILE];
+        let mut enemy = [0f32; TILE];
+        for k in 0..n {
+            friendly[k] = decayed[k] as f32;
+            enemy[k] = decayed[k] as f32 * self.defense(start + k, owners[start + k]);
+        }
+
+        // What the rows above, through and below the run attack with, from
+        // one cell before it to one after.
+        let mut attack = [[0f32; TILE + 2]; 3];
+        for (row, attack) in attack.iter_mut().enumerate() {
+            let from = start + row * self.width - self.width - 1;
+            for (k, attack) in attack.iter_mut().enumerate().take(n + 2) {
+                let (owner, troops) = (owners[from + k], troops[from + k]);
+                *attack = troops as f32 * self.traits(owner).attack;
+            }
+        }
+
+        // Bit `d` is set if neighbor `d` is stronger.
+        let mut stronger = [0u8; TILE];
+        for (d, (dx, dy)) in NEIGHBORS.into_iter().enumerate() {
+            let from = (start as isize + offsets[d]) as usize;
+            let neighbor_owners = &owners[from..from + n];
+            let neighbor_troops = &troops[from..from + n];
+            let column = (dx + 1) as usize;
+            let neighbor_attack = &attack[(dy + 1) as usize][column..column + n];
+            for k in 0..n {
+                let (bar, strength) = if neighbor_owners[k] == owners[start + k] {
+                    (friendly[k], neighbor_troops[k] as f32)
+                } else {
+                    (enemy[k], neighbor_attack[k])
+                };
+                let stronger_here = neighbor_owners[k] != 0 && strength > bar;
+                stronger[k] |= (stronger_here as u8) << d;
+            }
+        }
+
+        for k in 0..n {
+            let i = start + k;
+            let mut rng = CellRng::new(self.seed, self.tick, i);
+            let attacker = Self::neighbor_order(&mut rng)
+                .into_iter()
+                .find(|&d| stronger[k] & (1 << d) != 0)
+                .map(|d| {
+                    let j = (i as isize + offsets[d]) as usize;
+                    Cell {
+                        owner: owners[j],
+                        troops: troops[j],
+                    }
+                });
+            let cell = self.resolve(
+                i,
+                Cell {
+                    owner: owners[i],
+                    troops: decayed[k],
+                },
+                attacker,
+                &mut rng,
+            );
+            owners_out[k] = cell.owner;
+            troops_out[k] = cell.troops;
+        }
+    }
+
+    /// The rules for a single cell, given its neighbors (`None` past the edge of
+    /// a bounded world).
+    fn next_cell(&self, i: usize, neighbors: [Option<Cell>; 8]) -> Cell {
+        #[cfg(feature = "scripting")]
+        if let Some(cell) = self
+            .script
+            .as_ref()
+            .and_then(|script| script.next_cell(self, i, neighbors))
+        {
+            return cell;
+        }
+
+        let mut rng = CellRng::new(self.seed, self.tick, i);
+        let mut cell = self.cells.get(i);
+        cell.troops = self.rest(i, cell);
+
+        let attacker = self
+            .attacker(i, cell, neighbors, &mut rng)
+            .map(|(d, mut attacker)| {
+                if attacker.owner != cell.owner && self.params.combat.has_losses() {
+                    // An army attacking several cells at once splits between them.
+                    if let Some(from) = self.neighbor_indices(i)[d] {
+                        attacker.troops /= self.targets(from).max(1) as Troops;
+                    }
+                }
+                attacker
+            });
+        let next = self.resolve(i, cell, attacker, &mut rng);
+        if next.owner == cell.owner && next.owner != 0 && self.params.combat.has_losses() {
+            return Cell {
+                troops: self.cap(next.troops as f32 - self.losses(i)),
+                ..next
+            };
+        }
+        next
+    }
+
+    /// The first neighbor stronger than cell `i`, with its direction. `cell` is
+    /// cell `i` after its decay.
+    fn attacker(
+        &self,
+        i: usize,
+        cell: Cell,
+        neighbors: [Option<Cell>; 8],
+        rng: &mut CellRng,
+    ) -> Option<(usize, Cell)> {
+        Self::neighbor_order(rng)
+            .into_iter()
+            .filter_map(|d| Some((d, neighbors[d]?)))
+            .find(|&(_, neighbor)| self.stronger(i, cell, neighbor))
+    }
+
+    /// The troops cell `i` commits this tick attacking its enemy neighbors. Win
+    /// or lose, the whole army marches out, split between its targets: the
+    /// survivors of a win hold the new cells, so they don't stay behind as well.
+    fn losses(&self, i: usize) -> f32 {
+        if self.targets(i) > 0 {
+            self.cells.get(i).troops as f32
+        } else {
+            0.0
+        }
     }
 
-    pub fn get(&self, x: isize, y: isize) -> Option<&Cell> {
-        // if x < 0 || x >= self.width as isize || y < 0 || y >= self.height as isize {
-        //     None
-        // } else {
-        Some(
-            &self.cells[(y.rem_euclid(self.height as isize) as usize) * self.width
-                + (x.rem_euclid(self.width as isize) as usize)],
-        )
-        // }
+    /// How many of cell `i`'s enemy neighbors it attacks this tick, found by
+    /// picking their attackers the same way their own updates do.
+    fn targets(&self, i: usize) -> usize {
+        let owner = self.cells.get(i).owner;
+        let mut targets = 0;
+        for (d, j) in self.neighbor_indices(i).into_iter().enumerate() {
+            let Some(j) = j else { continue };
+            let mut defender = self.cells.get(j);
+            if defender.owner == owner || !self.terrain[j].claimable() {
+                continue;
+            }
+            defender.troops = self.rest(j, defender);
+            let mut rng = CellRng::new(self.seed, self.tick, j);
+            if let Some((from, _)) = self.attacker(j, defender, self.neighbors(j), &mut rng) {
+                targets += (from == OPPOSITE[d]) as usize;
+            }
+        }
+        targets
+    }
+
+    /// The random order in which a cell looks at its neighbors.
+    pub(crate) fn neighbor_order(rng: &mut CellRng) -> [usize; 8] {
+        let mut order = [0, 1, 2, 3, 4, 5, 6, 7];
+        rng.shuffle(&mut order);
+        order
+    }
+
+    /// A cell's troops before any fighting: owned cells decay, and unclaimed
+    /// ones grow back toward their garrison.
+    fn rest(&self, i: usize, cell: Cell) -> Troops {
+        if cell.owner != 0 {
+            return self.decay(cell.owner, cell.troops);
+        }
+        let garrison = self.garrison(i);
+        if cell.troops >= garrison {
+            return cell.troops.min(self.params.max_troops);
+        }
+        let missing = garrison - cell.troops;
+        let regrown = (missing as f32 * self.params.regrowth.clamp(0.0, 1.0)).ceil() as Troops;
+        cell.troops + regrown.min(missing)
+    }
+
+    /// `troops` of `owner` after a tick of decay. Its traits and the season
+    /// scale the part that is lost.
+    fn decay(&self, owner: u16, troops: Troops) -> Troops {
+        let decay = self.params.decay;
+        let scale = self.traits(owner).decay * self.seasonal.decay;
+        let kept = decay - (1.0 - decay) * (scale - 1.0);
+        self.cap(troops as f32 * kept)
+    }
+
+    /// Rounds `troops` down into `0..=params.max_troops`.
+    fn cap(&self, troops: f32) -> Troops {
+        // The upper bound can round up past `Troops::MAX` as a float, which
+        // the cast saturates back down.
+        troops.clamp(0.0, self.params.max_troops as f32) as Troops
+    }
+
+    /// Whether `neighbor` gets to take over cell `i`, after its decay.
+    /// Unclaimed cells never attack.
+    fn stronger(&self, i: usize, cell: Cell, neighbor: Cell) -> bool {
+        if neighbor.owner == 0 {
+            return false;
+        }
+        let (attack, defense) = if neighbor.owner == cell.owner {
+            (1.0, 1.0)
+        } else {
+            (
+                self.traits(neighbor.owner).attack,
+                self.defense(i, cell.owner),
+            )
+        };
+        neighbor.troops as f32 * attack > cell.troops as f32 * defense
+    }
+
+    /// The traits of `owner` this tick, neutral for the wild.
+    pub(crate) fn traits(&self, owner: u16) -> &Traits {
+        match owner {
+            0 => &Traits::NEUTRAL,
+            _ => self
+                .effective
+                .get(owner as usize - 1)
+                .unwrap_or(&Traits::NEUTRAL),
+        }
+    }
+
+    /// What troops of `owner` in cell `i` count for against its enemies.
+    fn defense(&self, i: usize, owner: u16) -> f32 {
+        self.traits(owner).defense(self.terrain[i])
+    }
+
+    /// The new cell `i`, given its decayed state and the first stronger neighbor.
+    fn resolve(&self, i: usize, mut cell: Cell, attacker: Option<Cell>, rng: &mut CellRng) -> Cell {
+        let params = &self.params;
+        match attacker {
+            Some(attacker) if attacker.owner == cell.owner || !params.combat.has_losses() => {
+                // Beating the wild's garrison costs what it took.
+                let garrison = if cell.owner == 0 {
+                    cell.troops as f32 * self.terrain[i].defense()
+                } else {
+                    0.0
+                };
+                let growth = self.traits(attacker.owner).growth * self.seasonal.growth;
+                cell.owner = attacker.owner;
+                cell.troops = self.cap(
+                    attacker.troops as f32
+                        * (rng.range(params.takeover_min, params.takeover_max) * growth)
+                        - garrison,
+                );
+            }
+            Some(attacker) if self.terrain[i].claimable() => {
+                let attack = self.traits(attacker.owner).attack;
+                let outcome = params.combat.fight(
+                    attacker.troops as f32 * attack,
+                    cell.troops as f32,
+                    self.defense(i, cell.owner),
+                    rng,
+                );
+                if outcome.attacker > 0.0 {
+                    cell.owner = attacker.owner;
+                    cell.troops = self.cap(outcome.attacker / attack);
+                } else {
+                    cell.troops = self.cap(outcome.defender);
+                }
+            }
+            _ => {}
+        }
+
+        if !self.terrain[i].claimable() {
+            cell.owner = 0;
+            cell.troops = 0;
+        }
+
+        cell
+    }
+
+    /// The indices of cell `i`'s neighbors, in the order of `NEIGHBORS`.
+    pub(crate) fn neighbor_indices(&self, i: usize) -> [Option<usize>; 8] {
+        let (x, y) = ((i % self.width) as isize, (i / self.width) as isize);
+        NEIGHBORS.map(|(dx, dy)| self.index(x + dx, y + dy))
+    }
+
+    fn neighbors(&self, i: usize) -> [Option<Cell>; 8] {
+        self.neighbor_indices(i)
+            .map(|j| j.map(|j| self.cells.get(j)))
+    }
+
+    /// The index of the cell at `x`, `y`, wrapping around or not depending on
+    /// the topology.
+    fn index(&self, x: isize, y: isize) -> Option<usize> {
+        match self.topology {
+            Topology::Torus => Some(
+                (y.rem_euclid(self.height as isize) as usize) * self.width
+                    + (x.rem_euclid(self.width as isize) as usize),
+            ),
+            Topology::Bounded => {
+                if x < 0 || x >= self.width as isize || y < 0 || y >= self.height as isize {
+                    None
+                } else {
+                    Some((y as usize) * self.width + (x as usize))
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<Cell> {
+        self.index(x, y).map(|i| self.cells.get(i))
     }
     pub fn set(&mut self, x: isize, y: isize, val: Cell) {
         assert!(x >= 0 && x < (self.width as isize));
         assert!(y >= 0 && y < (self.height as isize));
 
-        self.cells[(y as usize) * self.width + (x as usize)] = val;
+        self.cells
+            .set((y as usize) * self.width + (x as usize), val);
     }
 
-    /// Draw the `World` state to the frame buffer.
+    /// Draw the `World` state to the frame buffer. Cells get brighter the closer
+    /// they are to `params.max_troops`.
     ///
     /// Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
     pub fn draw(&self, frame: &mut [u8]) {
+        let max = self.params.max_troops.max(1) as f32;
         for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            let x = i % self.width as usize;
-            let y = i / self.width as usize;
-
-            let cell = self.get(x as isize, y as isize).unwrap();
+            let cell = self.cells.get(i);
 
             let rgba = if cell.owner != 0 {
                 let color = self.empires[(cell.owner - 1) as usize].color;
+                let brightness = (cell.troops as f32 / max).min(1.0);
                 [
-                    (color.0 as f32 * (cell.troops as f32 / 65355.0)) as u8,
-                    (color.1 as f32 * (cell.troops as f32 / 65355.0)) as u8,
-                    (color.2 as f32 * (cell.troops as f32 / 65355.0)) as u8,
+                    (color.0 as f32 * brightness) as u8,
+                    (color.1 as f32 * brightness) as u8,
+                    (color.2 as f32 * brightness) as u8,
                     color.3,
                 ]
             } else {
-                [0x00, 0x00, 0x00, 0xff]
+                self.terrain[i].color()
             };
 
             pixel.copy_from_slice(&rgba);
         }
     }
+
+    /// Returns the id of the winning empire, if any of `self.victory` is met.
+    pub fn winner(&self) -> Option<u16> {
+        if self.empires.is_empty() {
+            return None;
+        }
+
+        let stats = self.stats();
+        let leader = stats.iter().max_by_key(|stats| stats.cells)?.id;
+
+        self.victory.iter().find_map(|condition| match *condition {
+            VictoryCondition::LastStanding => {
+                let mut alive = stats.iter().filter(|stats| stats.cells > 0);
+                match (alive.next(), alive.next()) {
+                    (Some(stats), None) if self.empires.len() > 1 => Some(stats.id),
+                    _ => None,
+                }
+            }
+            VictoryCondition::Territory { share } => {
+                let claimable = self.terrain.iter().filter(|t| t.claimable()).count();
+                let cells = stats[leader as usize - 1].cells;
+                (cells as f32 >= claimable as f32 * share).then_some(leader)
+            }
+            VictoryCondition::TickLimit { ticks } => (self.tick >= ticks).then_some(leader),
+        })
+    }
+
+    /// A hash of the tick, cells, terrain, garrisons and empires, which is the
+    /// same on every platform and for either cell layout. Worlds that play out
+    /// the same way have the same checksums.
+    pub fn checksum(&self) -> u64 {
+        let mut checksum = Checksum::new();
+        checksum.add(self.tick as u64);
+        checksum.add_cells(&self.cells);
+        for (terrain, &garrison) in self.terrain.iter().zip(&self.garrisons) {
+            checksum.add((garrison as u64) << 8 | *terrain as u64);
+        }
+        for empire in &self.empires {
+            let (r, g, b, a) = empire.color;
+            checksum.add(u64::from_le_bytes([r, g, b, a, 0, 0, 0, 0]) | (empire.id as u64) << 32);
+            checksum.add(empire.name.len() as u64);
+            for byte in empire.name.bytes() {
+                checksum.add(byte as u64);
+            }
+            checksum.add_traits(&empire.traits);
+            checksum.add(empire.tech.research);
+            checksum.add(empire.tech.tier as u64);
+        }
+        checksum.0
+    }
 }
 
-#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// FNV-1a, over 64-bit words instead of bytes.
+struct Checksum(u64);
+impl Checksum {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn add(&mut self, value: u64) {
+        self.0 = (self.0 ^ value).wrapping_mul(0x100_0000_01b3);
+    }
+
+    fn add_traits(&mut self, traits: &Traits) {
+        let Traits {
+            attack,
+            defense,
+            decay,
+            growth,
+            affinities,
+        } = traits;
+        for value in [
+            attack,
+            defense,
+            decay,
+            growth,
+            &affinities.plains,
+            &affinities.hills,
+            &affinities.mountains,
+            &affinities.river,
+        ] {
+            self.add(value.to_bits() as u64);
+        }
+    }
+
+    fn add_cells(&mut self, cells: &Cells) {
+        self.add(cells.len() as u64);
+        // Wide troops go above the owner, so narrow ones hash the same either way.
+        for cell in cells.iter() {
+            let troops = cell.troops as u64;
+            self.add((troops >> 16) << 32 | (cell.owner as u64) << 16 | (troops & 0xffff));
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// edges wrap around
+    #[default]
+    Torus,
+    /// cells past the edges don't exist
+    Bounded,
+}
+
+/// Tunable constants for `World::update`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimParams {
+    /// fraction of troops a cell keeps each tick
+    pub decay: f32,
+    /// range of the random multiplier applied to troops when a cell is taken over
+    pub takeover_min: f32,
+    pub takeover_max: f32,
+    /// Most troops a cell can hold. Decay and takeovers round down and then
+    /// saturate at this, so troops never wrap around. Cells set above it from
+    /// outside are cut down on their next update.
+    pub max_troops: Troops,
+    /// how battles between empires play out
+    pub combat: Combat,
+    /// fraction of an interior cell's troops that moves toward the border each
+    /// tick, see [`World::frontier_distances`]
+    pub flow: f32,
+    /// fraction of troops that cells cut off from their empire's main body
+    /// lose each tick, see [`World::components`]
+    pub attrition: f32,
+    pub rebellion: RebellionParams,
+    pub tech: TechParams,
+    /// fraction of what unclaimed cells are missing of their garrison that
+    /// grows back each tick, 0 for garrisons that stay beaten down
+    pub regrowth: f32,
+    /// seasons and other events, see [`crate::events`]
+    pub events: Vec<Scheduled>,
+    /// how far empires see around their territory, 0 for no fog of war
+    pub vision: usize,
+}
+impl Default for SimParams {
+    fn default() -> Self {
+        Self {
+            decay: 0.95,
+            takeover_min: 0.98,
+            takeover_max: 1.01,
+            max_troops: Troops::MAX,
+            combat: Combat::Takeover,
+            flow: 0.0,
+            attrition: 0.0,
+            rebellion: RebellionParams::default(),
+            tech: TechParams::default(),
+            regrowth: 0.0,
+            events: vec![],
+            vision: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum VictoryCondition {
+    /// all other empires have no cells left
+    LastStanding,
+    /// an empire holds at least `share` (0..=1) of the claimable cells
+    Territory { share: f32 },
+    /// the empire with the most cells when `ticks` is reached
+    TickLimit { ticks: usize },
+}
+
+/// The troops in a cell, `u32` with the `wide-troops` feature.
+#[cfg(not(feature = "wide-troops"))]
+pub type Troops = u16;
+#[cfg(feature = "wide-troops")]
+pub type Troops = u32;
+
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(C)]
 pub struct Cell {
     pub owner: u16, // 0 = unclaimed
-    pub troops: u16,
+    pub troops: Troops,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Empire {
     pub id: u16, // from 1
+    pub name: String,
     pub color: (u8, u8, u8, u8),
+    #[serde(default)]
+    pub traits: Traits,
+    #[serde(default)]
+    pub tech: Tech,
+}
+impl Empire {
+    /// An empire with neutral traits that hasn't researched anything yet.
+    pub fn new(id: u16, name: impl Into<String>, color: (u8, u8, u8, u8)) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            color,
+            traits: Traits::default(),
+            tech: Tech::default(),
+        }
+    }
 }
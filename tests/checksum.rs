@@ -0,0 +1,118 @@
+use libterritory::{
+    cells::Layout,
+    replay::{Intervention, Player, Replay},
+    spawn::SpawnStrategy,
+    terrain::Terrain,
+    world::{Cell, Empire, Topology, World},
+};
+
+mod common;
+
+fn world(topology: Topology) -> World {
+    common::world(topology, 9, SpawnStrategy::Circle)
+}
+
+#[test]
+fn checksum_is_stable() {
+    let mut world = World::new(3, 2);
+    world.seed = 0;
+    world.empires.push(Empire::new(1, "Red", (255, 0, 0, 255)));
+    world.set(
+        1,
+        1,
+        Cell {
+            owner: 1,
+            troops: 500,
+        },
+    );
+    assert_eq!(world.checksum(), 0xb52b_f419_ac2b_c388);
+}
+
+#[test]
+fn checksum_ignores_layout_but_not_state() {
+    let mut world = world(Topology::Torus);
+    let checksum = world.checksum();
+    world.set_layout(Layout::StructOfArrays);
+    assert_eq!(world.checksum(), checksum);
+
+    let mut changed = world.clone();
+    changed.set(
+        5,
+        5,
+        Cell {
+            owner: 2,
+            troops: 1,
+        },
+    );
+    assert_ne!(changed.checksum(), checksum);
+
+    let mut changed = world.clone();
+    changed.empires[0].color.0 = 0;
+    assert_ne!(changed.checksum(), checksum);
+
+    let mut changed = world.clone();
+    changed.tick += 1;
+    assert_ne!(changed.checksum(), checksum);
+
+    let changes: [fn(&mut World); 6] = [
+        |world| world.empires[1].tech.research += 1,
+        |world| world.empires[1].tech.tier += 1,
+        |world| world.empires[2].traits.attack = 1.1,
+        |world| world.empires[2].traits.affinities.river = 0.9,
+        |world| world.garrisons[7] = 3,
+        |world| world.terrain[7] = Terrain::Mountains,
+    ];
+    for (n, change) in changes.iter().enumerate() {
+        let mut changed = world.clone();
+        change(&mut changed);
+        assert_ne!(changed.checksum(), checksum, "change {}", n);
+    }
+}
+
+#[test]
+fn serial_matches_parallel() {
+    for topology in [Topology::Torus, Topology::Bounded] {
+        for layout in Layout::ALL {
+            let mut world = world(topology);
+            world.set_layout(layout);
+            world.check_parallel = true;
+            for _ in 0..60 {
+                world.update();
+            }
+            assert_eq!(world.parallel_mismatch, None, "{:?} {:?}", topology, layout);
+        }
+    }
+}
+
+#[test]
+fn playback_catches_changed_replays() {
+    let mut world = world(Topology::Bounded);
+    let mut replay = Replay::new(&world);
+    for _ in 0..300 {
+        world.update();
+        replay.tick(&world);
+        if world.tick == 100 {
+            let paint = Intervention::Paint {
+                x: 50,
+                y: 35,
+                radius: 5,
+                cell: Cell {
+                    owner: 3,
+                    troops: 50_000,
+                },
+            };
+            replay.record(&mut world, paint);
+        }
+    }
+    assert!(replay.checksums.len() >= 4);
+
+    let mut player = Player::new(replay.clone());
+    player.seek(300);
+    assert_eq!(player.desync(), None);
+    assert_eq!(player.world().checksum(), world.checksum());
+
+    replay.events[0].tick = 101;
+    let mut player = Player::new(replay);
+    player.seek(300);
+    assert_eq!(player.desync(), Some(128));
+}
@@ -0,0 +1,342 @@
+use libterritory::{
+    cells::Layout,
+    combat::Combat,
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    terrain::{Terrain, TerrainGenerator},
+    world::{Cell, Topology, Troops, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+use common::empires;
+
+/// Two cells side by side, empire 1 attacking empire 2, which is on `terrain`.
+fn duel(combat: Combat, attacker: Troops, defender: Troops, terrain: Terrain) -> World {
+    let mut world = World::new(2, 1);
+    world.seed = 4;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.combat = combat;
+    world.empires = empires(2);
+    world.terrain[1] = terrain;
+    world.set(
+        0,
+        0,
+        Cell {
+            owner: 1,
+            troops: attacker,
+        },
+    );
+    world.set(
+        1,
+        0,
+        Cell {
+            owner: 2,
+            troops: defender,
+        },
+    );
+    world
+}
+
+fn cells(world: &World) -> [Cell; 2] {
+    [world.get(0, 0).unwrap(), world.get(1, 0).unwrap()]
+}
+
+#[test]
+fn takeovers_cost_nothing() {
+    let mut world = duel(Combat::Takeover, 1000, 100, Terrain::Plains);
+    world.params.takeover_min = 1.0;
+    world.params.takeover_max = 1.0;
+    world.update();
+    assert_eq!(
+        cells(&world),
+        [
+            Cell {
+                owner: 1,
+                troops: 1000
+            },
+            Cell {
+                owner: 1,
+                troops: 1000
+            }
+        ]
+    );
+}
+
+/// The troops all of the world's cells hold between them.
+fn total(world: &World) -> u64 {
+    world.cells.iter().map(|cell| cell.troops as u64).sum()
+}
+
+/// Empire 1 in the middle of a `size` by `size` square of empire 2.
+fn surrounded(combat: Combat, size: usize, attacker: Troops, defender: Troops) -> World {
+    let mut world = World::new(size, size);
+    world.seed = 4;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.combat = combat;
+    world.empires = empires(2);
+    world.cells.fill(Cell {
+        owner: 2,
+        troops: defender,
+    });
+    world.set(
+        size as isize / 2,
+        size as isize / 2,
+        Cell {
+            owner: 1,
+            troops: attacker,
+        },
+    );
+    world
+}
+
+#[test]
+fn lanchester_linear_keeps_the_difference() {
+    let mut world = duel(
+        Combat::Lanchester { exponent: 1.0 },
+        1000,
+        100,
+        Terrain::Plains,
+    );
+    world.update();
+    assert_eq!(
+        cells(&world),
+        [
+            Cell {
+                owner: 1,
+                troops: 0
+            },
+            Cell {
+                owner: 1,
+                troops: 900
+            }
+        ]
+    );
+
+    // Defenders on hills count one and a half times over.
+    let mut world = duel(
+        Combat::Lanchester { exponent: 1.0 },
+        1000,
+        100,
+        Terrain::Hills,
+    );
+    world.update();
+    assert_eq!(cells(&world)[0].troops, 0);
+    assert_eq!(cells(&world)[1].troops, 850);
+}
+
+#[test]
+fn lanchester_square_favors_the_bigger_army() {
+    let mut world = duel(
+        Combat::Lanchester { exponent: 2.0 },
+        500,
+        300,
+        Terrain::Plains,
+    );
+    world.update();
+    assert_eq!(
+        cells(&world),
+        [
+            Cell {
+                owner: 1,
+                troops: 0
+            },
+            Cell {
+                owner: 1,
+                troops: 400
+            }
+        ]
+    );
+}
+
+#[test]
+fn battles_never_make_troops() {
+    for combat in [
+        Combat::Lanchester { exponent: 1.0 },
+        Combat::Lanchester { exponent: 2.0 },
+        Combat::Dice { sides: 6 },
+    ] {
+        for (attacker, defender) in [(1000, 100), (500, 300), (120, 100), (300, 299)] {
+            for terrain in [Terrain::Plains, Terrain::Hills] {
+                for seed in 0..20 {
+                    let mut world = duel(combat, attacker, defender, terrain);
+                    world.seed = seed;
+                    let before = total(&world);
+                    world.update();
+                    assert!(
+                        total(&world) <= before,
+                        "{:?}, {} vs {} on {:?}: {:?}",
+                        combat,
+                        attacker,
+                        defender,
+                        terrain,
+                        cells(&world)
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn armies_split_between_their_targets() {
+    let mut world = World::new(3, 1);
+    world.seed = 4;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.combat = Combat::Lanchester { exponent: 1.0 };
+    world.empires = empires(2);
+    for (x, owner, troops) in [(0, 2, 100), (1, 1, 1000), (2, 2, 100)] {
+        world.set(x, 0, Cell { owner, troops });
+    }
+    world.update();
+    let row = (0..3).map(|x| world.get(x, 0).unwrap()).collect::<Vec<_>>();
+    assert_eq!(
+        row,
+        [
+            Cell {
+                owner: 1,
+                troops: 400
+            },
+            Cell {
+                owner: 1,
+                troops: 0
+            },
+            Cell {
+                owner: 1,
+                troops: 400
+            }
+        ]
+    );
+
+    // Taking on all eight neighbors at once doesn't make troops either.
+    for combat in [
+        Combat::Lanchester { exponent: 1.0 },
+        Combat::Lanchester { exponent: 2.0 },
+        Combat::Dice { sides: 6 },
+    ] {
+        for (attacker, defender) in [(8000, 100), (3000, 300), (1000, 10)] {
+            for seed in 0..20 {
+                let mut world = surrounded(combat, 3, attacker, defender);
+                world.seed = seed;
+                let before = total(&world);
+                world.update();
+                assert!(
+                    total(&world) <= before,
+                    "{:?}, {} vs {}: {} > {}",
+                    combat,
+                    attacker,
+                    defender,
+                    total(&world),
+                    before
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn dice_can_go_either_way() {
+    let (mut wins, mut losses) = (0, 0);
+    for seed in 0..200 {
+        let mut world = duel(Combat::Dice { sides: 6 }, 120, 100, Terrain::Plains);
+        world.seed = seed;
+        world.update();
+        let [attacker, defender] = cells(&world);
+        assert_eq!(attacker.owner, 1);
+        // The whole army marched out either way.
+        assert_eq!(attacker.troops, 0);
+        if defender.owner == 1 {
+            wins += 1;
+            assert!(defender.troops <= 120);
+        } else {
+            losses += 1;
+            assert!(defender.troops <= 100);
+        }
+        assert!(total(&world) <= 220);
+    }
+    assert!(wins > 0 && losses > 0, "{} wins, {} losses", wins, losses);
+}
+
+fn battlefield(combat: Combat, topology: Topology) -> World {
+    let mut world = World::new(80, 60);
+    world.seed = 6;
+    world.topology = topology;
+    world.params.combat = combat;
+    world.terrain = TerrainGenerator {
+        seed: 6,
+        scale: 25.0,
+        ..Default::default()
+    }
+    .generate(80, 60);
+    world.empires = empires(4);
+    Spawn {
+        strategy: SpawnStrategy::Territories,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(6));
+    world
+}
+
+#[test]
+fn battles_are_deterministic() {
+    for combat in [
+        Combat::Lanchester { exponent: 1.5 },
+        Combat::Dice { sides: 6 },
+    ] {
+        for topology in [Topology::Torus, Topology::Bounded] {
+            let mut world = battlefield(combat, topology);
+            world.check_parallel = true;
+            let mut full = world.clone();
+            let mut soa = world.clone();
+            soa.set_layout(Layout::StructOfArrays);
+            for tick in 0..30 {
+                world.update();
+                full.update_full();
+                soa.update();
+                assert!(world.cells == full.cells, "{:?} tick {}", combat, tick);
+                assert!(
+                    world.cells.iter().eq(soa.cells.iter()),
+                    "{:?} tick {}",
+                    combat,
+                    tick
+                );
+            }
+            assert_eq!(world.parallel_mismatch, None);
+        }
+    }
+}
+
+#[test]
+fn battles_cost_troops() {
+    let total = |world: &World| world.stats().iter().map(|s| s.troops).sum::<u64>();
+    let mut takeover = battlefield(Combat::Takeover, Topology::Torus);
+    let mut lanchester = battlefield(Combat::Lanchester { exponent: 1.0 }, Topology::Torus);
+    for _ in 0..40 {
+        takeover.update();
+        lanchester.update();
+    }
+    assert!(total(&lanchester) < total(&takeover));
+}
+
+#[test]
+fn combat_is_set_in_scenarios() {
+    let scenario = Scenario::from_toml(
+        r#"
+        width = 10
+        height = 10
+
+        [params.combat]
+        type = "dice"
+        sides = 20
+        "#,
+    )
+    .unwrap();
+    assert_eq!(scenario.params.combat, Combat::Dice { sides: 20 });
+    assert_eq!(
+        scenario.build().unwrap().params.combat,
+        Combat::Dice { sides: 20 }
+    );
+}
@@ -0,0 +1,35 @@
+use libterritory::{
+    spawn::{Spawn, SpawnStrategy},
+    terrain::TerrainGenerator,
+    world::{Empire, Topology, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+/// `n` white empires, numbered from 1.
+pub fn empires(n: u16) -> Vec<Empire> {
+    (1..=n)
+        .map(|id| Empire::new(id, format!("Empire {}", id), (255, 255, 255, 255)))
+        .collect()
+}
+
+/// A 150x110 world with terrain and five empires placed by `strategy`, all
+/// from `seed`.
+#[allow(dead_code)] // not every test uses it
+pub fn world(topology: Topology, seed: u64, strategy: SpawnStrategy) -> World {
+    let mut world = World::new(150, 110);
+    world.seed = seed;
+    world.topology = topology;
+    world.terrain = TerrainGenerator {
+        seed,
+        scale: 30.0,
+        ..Default::default()
+    }
+    .generate(150, 110);
+    world.empires = empires(5);
+    Spawn {
+        strategy,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(seed));
+    world
+}
@@ -0,0 +1,212 @@
+use libterritory::{
+    cells::Layout,
+    events::{Event, Scheduled},
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    terrain::TerrainGenerator,
+    world::{Cell, Topology, Troops, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+use common::empires;
+
+/// Cells of empire 1 holding `troops` each, and nothing else.
+fn kingdom(width: usize, height: usize, troops: &[Troops]) -> World {
+    let mut world = World::new(width, height);
+    world.seed = 4;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.takeover_min = 1.0;
+    world.params.takeover_max = 1.0;
+    world.empires = empires(1);
+    for (i, &troops) in troops.iter().enumerate() {
+        world.cells.set(i, Cell { owner: 1, troops });
+    }
+    world
+}
+
+fn once(event: Event, start: usize, duration: usize) -> Scheduled {
+    Scheduled {
+        event,
+        start,
+        every: 0,
+        duration,
+    }
+}
+
+#[test]
+fn events_follow_their_schedule() {
+    let winter = Event::Winter { decay: 2.0 };
+    let yearly = Scheduled {
+        event: winter,
+        start: 5,
+        every: 10,
+        duration: 3,
+    };
+    let on = (0..30)
+        .filter(|&tick| yearly.active(tick))
+        .collect::<Vec<_>>();
+    assert_eq!(on, [5, 6, 7, 15, 16, 17, 25, 26, 27]);
+
+    let on = (0..30)
+        .filter(|&tick| once(winter, 2, 2).active(tick))
+        .collect::<Vec<_>>();
+    assert_eq!(on, [2, 3]);
+
+    let mut world = World::new(1, 1);
+    world.params.events = vec![yearly, once(Event::Harvest { growth: 1.1 }, 6, 1)];
+    world.tick = 6;
+    assert_eq!(
+        world.active_events().collect::<Vec<_>>(),
+        [winter, Event::Harvest { growth: 1.1 }]
+    );
+    world.tick = 8;
+    assert_eq!(world.active_events().count(), 0);
+}
+
+#[test]
+fn winter_raises_decay() {
+    let mut world = kingdom(1, 1, &[1000]);
+    world.params.decay = 0.75;
+    world.params.events = vec![once(Event::Winter { decay: 2.0 }, 0, 1)];
+    world.update();
+    assert_eq!(world.cells.get(0).troops, 500);
+    world.update();
+    assert_eq!(world.cells.get(0).troops, 375);
+}
+
+#[test]
+fn harvests_grow_takeovers() {
+    let mut world = kingdom(2, 1, &[1000]);
+    world.params.events = vec![once(Event::Harvest { growth: 1.5 }, 0, 1)];
+    world.update();
+    assert_eq!(
+        world.cells.get(1),
+        Cell {
+            owner: 1,
+            troops: 1500
+        }
+    );
+}
+
+#[test]
+fn plague_hits_dense_regions() {
+    // A crowded column on the left and a sparse one on the right, with an
+    // empty one in between.
+    let mut world = kingdom(5, 3, &[]);
+    for y in 0..3 {
+        for (x, troops) in [(0, 1000), (1, 1000), (3, 100), (4, 100)] {
+            world.set(x, y, Cell { owner: 1, troops });
+        }
+    }
+    world.params.events = vec![once(
+        Event::Plague {
+            density: 500,
+            loss: 0.5,
+        },
+        0,
+        1,
+    )];
+    world.update();
+    for y in 0..3 {
+        assert_eq!(world.get(0, y).unwrap().troops, 500);
+        assert_eq!(world.get(4, y).unwrap().troops, 100);
+    }
+
+    world.update();
+    assert_eq!(world.get(0, 0).unwrap().troops, 500);
+}
+
+#[test]
+fn events_are_deterministic() {
+    let mut world = World::new(80, 60);
+    world.seed = 11;
+    world.terrain = TerrainGenerator {
+        seed: 11,
+        scale: 25.0,
+        ..Default::default()
+    }
+    .generate(80, 60);
+    world.empires = empires(4);
+    world.params.events = vec![
+        Scheduled {
+            event: Event::Winter { decay: 3.0 },
+            start: 0,
+            every: 10,
+            duration: 4,
+        },
+        Scheduled {
+            event: Event::Harvest { growth: 1.05 },
+            start: 5,
+            every: 10,
+            duration: 3,
+        },
+        once(
+            Event::Plague {
+                density: 3000,
+                loss: 0.1,
+            },
+            12,
+            6,
+        ),
+    ];
+    Spawn {
+        strategy: SpawnStrategy::Territories,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(11));
+    world.check_parallel = true;
+
+    let mut full = world.clone();
+    let mut soa = world.clone();
+    soa.set_layout(Layout::StructOfArrays);
+    for tick in 0..30 {
+        world.update();
+        full.update_full();
+        soa.update();
+        assert!(world.cells == full.cells, "tick {}", tick);
+        assert!(world.cells.iter().eq(soa.cells.iter()), "tick {}", tick);
+    }
+    assert_eq!(world.parallel_mismatch, None);
+}
+
+#[test]
+fn events_are_set_in_scenarios() {
+    let scenario = Scenario::from_toml(
+        r#"
+        width = 4
+        height = 4
+
+        [[params.events]]
+        start = 750
+        every = 1000
+        duration = 250
+        event = { type = "winter", decay = 3.0 }
+
+        [[params.events]]
+        duration = 100
+        event = { type = "plague", density = 20000, loss = 0.05 }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        scenario.build().unwrap().params.events,
+        [
+            Scheduled {
+                event: Event::Winter { decay: 3.0 },
+                start: 750,
+                every: 1000,
+                duration: 250,
+            },
+            once(
+                Event::Plague {
+                    density: 20000,
+                    loss: 0.05,
+                },
+                0,
+                100
+            ),
+        ]
+    );
+}
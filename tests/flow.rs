@@ -0,0 +1,126 @@
+use libterritory::{
+    cells::Layout,
+    flow::UNREACHABLE,
+    spawn::{Spawn, SpawnStrategy},
+    terrain::{Terrain, TerrainGenerator},
+    world::{Cell, Topology, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+use common::empires;
+
+/// Empire 1 on the left five columns, empire 2 on the right two, with the same
+/// troops everywhere so that the rules alone change nothing.
+fn strip() -> World {
+    let mut world = World::new(7, 3);
+    world.seed = 2;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.flow = 0.5;
+    world.empires = empires(2);
+    for y in 0..3 {
+        for x in 0..7 {
+            let owner = if x < 5 { 1 } else { 2 };
+            world.set(
+                x,
+                y,
+                Cell {
+                    owner,
+                    troops: 1000,
+                },
+            );
+        }
+    }
+    world
+}
+
+#[test]
+fn distances_count_steps_to_the_border() {
+    let mut world = strip();
+    world.set(6, 0, Cell::default());
+    world.terrain[6] = Terrain::Water;
+    let distances = world.frontier_distances();
+    let row = |y: usize| distances[y * 7..y * 7 + 7].to_vec();
+    assert_eq!(row(0), [4, 3, 2, 1, 0, 0, UNREACHABLE]);
+    assert_eq!(row(2), [4, 3, 2, 1, 0, 0, 1]);
+}
+
+#[test]
+fn coastlines_arent_borders() {
+    let mut world = strip();
+    for y in 0..3 {
+        for x in 0..2 {
+            world.set(x, y, Cell::default());
+            world.terrain[(y * 7 + x) as usize] = Terrain::Water;
+        }
+    }
+    let distances = world.frontier_distances();
+    for y in 0..3 {
+        assert_eq!(
+            distances[y * 7..y * 7 + 7],
+            [UNREACHABLE, UNREACHABLE, 2, 1, 0, 0, 1]
+        );
+    }
+
+    // So troops on the coast head inland, toward the enemy.
+    world.update();
+    assert!(world.get(2, 1).unwrap().troops < 1000);
+}
+
+#[test]
+fn flow_moves_troops_to_the_border_and_keeps_totals() {
+    let mut world = strip();
+    let before = world.stats();
+    world.update();
+    assert_eq!(world.stats(), before);
+
+    let row = (0..7)
+        .map(|x| world.get(x, 1).unwrap().troops)
+        .collect::<Vec<_>>();
+    // The far edges send half, split three ways and rounded down, and the
+    // borders only receive.
+    assert_eq!(row[0], 1000 - 3 * 166);
+    assert!(row[4] > 1000);
+    assert!(row[5] > 1000);
+    assert_eq!(row[6], 1000 - 3 * 166);
+}
+
+#[test]
+fn no_flow_changes_nothing() {
+    let mut world = strip();
+    world.params.flow = 0.0;
+    let before = world.cells.clone();
+    world.update();
+    assert!(world.cells == before);
+}
+
+#[test]
+fn flow_is_deterministic() {
+    let mut world = World::new(80, 60);
+    world.seed = 8;
+    world.params.flow = 0.2;
+    world.terrain = TerrainGenerator {
+        seed: 8,
+        scale: 20.0,
+        ..Default::default()
+    }
+    .generate(80, 60);
+    world.empires = empires(4);
+    Spawn {
+        strategy: SpawnStrategy::Territories,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(8));
+
+    let mut full = world.clone();
+    let mut soa = world.clone();
+    soa.set_layout(Layout::StructOfArrays);
+    for tick in 0..40 {
+        world.update();
+        full.update_full();
+        soa.update();
+        assert!(world.cells == full.cells, "tick {}", tick);
+        assert!(world.cells.iter().eq(soa.cells.iter()), "tick {}", tick);
+    }
+}
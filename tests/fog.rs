@@ -0,0 +1,250 @@
+use libterritory::{
+    replay::Snapshot,
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    world::{Cell, Topology, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+use common::empires;
+
+fn world(width: usize, height: usize, topology: Topology, vision: usize) -> World {
+    let mut world = World::new(width, height);
+    world.seed = 6;
+    world.topology = topology;
+    world.params.decay = 1.0;
+    world.params.vision = vision;
+    world.empires = empires(2);
+    world
+}
+
+fn visible(world: &World, empire: u16) -> Vec<(usize, usize)> {
+    world
+        .visible_cells(empire)
+        .map(|(i, _)| (i % world.width, i / world.width))
+        .collect()
+}
+
+#[test]
+fn empires_see_around_their_territory() {
+    let mut world = world(9, 9, Topology::Bounded, 2);
+    world.set(
+        4,
+        4,
+        Cell {
+            owner: 1,
+            troops: 1,
+        },
+    );
+    world.set(
+        0,
+        0,
+        Cell {
+            owner: 2,
+            troops: 1,
+        },
+    );
+    world.update_visibility();
+
+    let seen = visible(&world, 1);
+    assert_eq!(seen.len(), 25);
+    assert!(seen.contains(&(6, 6)) && seen.contains(&(2, 2)));
+    assert!(!seen.contains(&(7, 4)));
+    assert_eq!(world.visibility[1].count(), 9);
+    assert!(!world.visible(2, world.width * 3));
+}
+
+#[test]
+fn vision_wraps_around_a_torus() {
+    let mut world = world(10, 10, Topology::Torus, 1);
+    world.set(
+        0,
+        0,
+        Cell {
+            owner: 1,
+            troops: 1,
+        },
+    );
+    world.update_visibility();
+    let seen = visible(&world, 1);
+    assert_eq!(seen.len(), 9);
+    assert!(seen.contains(&(9, 9)) && seen.contains(&(1, 9)));
+
+    // Vision wider than the world sees all of it.
+    world.params.vision = 5;
+    world.update_visibility();
+    assert_eq!(world.visibility[0].count(), 100);
+}
+
+#[test]
+fn without_fog_everyone_sees_everything() {
+    let mut world = world(6, 6, Topology::Torus, 0);
+    world.set(
+        0,
+        0,
+        Cell {
+            owner: 1,
+            troops: 1,
+        },
+    );
+    world.update();
+    assert!(world.visibility.is_empty());
+    assert_eq!(visible(&world, 2).len(), 36);
+
+    let mut frame = vec![0; 36 * 4];
+    let mut fogged = vec![0; 36 * 4];
+    world.draw(&mut frame);
+    world.draw_for(2, &mut fogged);
+    assert_eq!(frame, fogged);
+}
+
+/// Every cell within `vision` of one of `empire`'s, the slow way.
+fn brute_force(world: &World, empire: u16) -> Vec<bool> {
+    let (w, h) = (world.width as isize, world.height as isize);
+    let r = world.params.vision as isize;
+    (0..w * h)
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            (-r..=r).any(|dy| {
+                (-r..=r).any(|dx| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    let (nx, ny) = match world.topology {
+                        Topology::Torus => (nx.rem_euclid(w), ny.rem_euclid(h)),
+                        Topology::Bounded if nx < 0 || ny < 0 || nx >= w || ny >= h => {
+                            return false
+                        }
+                        Topology::Bounded => (nx, ny),
+                    };
+                    world.cells.get((ny * w + nx) as usize).owner == empire
+                })
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn visibility_follows_the_match() {
+    for topology in [Topology::Torus, Topology::Bounded] {
+        let mut world = world(40, 30, topology, 3);
+        world.params = Default::default();
+        world.params.vision = 3;
+        world.empires = empires(3);
+        Spawn {
+            strategy: SpawnStrategy::Random,
+            fair: false,
+        }
+        .apply(&mut world, &mut StdRng::seed_from_u64(6));
+        for _ in 0..20 {
+            world.update();
+            for empire in 1..=3 {
+                let expected = brute_force(&world, empire);
+                for (i, &expected) in expected.iter().enumerate() {
+                    assert_eq!(world.visible(empire, i), expected, "{:?} {}", topology, i);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn fog_hides_other_empires() {
+    let mut world = world(9, 1, Topology::Bounded, 2);
+    world.params.max_troops = 50_000;
+    world.empires[1].color = (0, 0, 255, 255);
+    world.set(
+        0,
+        0,
+        Cell {
+            owner: 1,
+            troops: 50_000,
+        },
+    );
+    world.set(
+        8,
+        0,
+        Cell {
+            owner: 2,
+            troops: 50_000,
+        },
+    );
+    world.update_visibility();
+
+    let mut frame = vec![0; 9 * 4];
+    let mut fogged = vec![0; 9 * 4];
+    world.draw(&mut frame);
+    world.draw_for(1, &mut fogged);
+    assert_eq!(frame[..12], fogged[..12]);
+    assert_ne!(frame[32..], fogged[32..]);
+    assert_eq!(fogged[32..], fogged[28..32]);
+    assert!(visible(&world, 1).iter().all(|&(x, _)| x <= 2));
+
+    // Observing the world leaves the hidden enemy out.
+    let observed = world.observe(1);
+    assert_eq!(observed.len(), 9);
+    assert_eq!(
+        observed[0],
+        Some(Cell {
+            owner: 1,
+            troops: 50_000
+        })
+    );
+    assert_eq!(observed[2], Some(Cell::default()));
+    assert!(observed[3..].iter().all(Option::is_none));
+    assert!(world
+        .observe(2)
+        .iter()
+        .flatten()
+        .all(|cell| cell.owner != 1));
+
+    // Without fog, nothing is left out.
+    world.params.vision = 0;
+    assert!(world
+        .observe(1)
+        .into_iter()
+        .eq(world.cells.iter().map(Some)));
+}
+
+#[test]
+fn new_worlds_start_with_their_fog() {
+    let scenario = Scenario::from_toml(
+        r#"
+        width = 12
+        height = 12
+
+        [params]
+        vision = 1
+
+        [[empires]]
+        color = [255, 0, 0]
+        start = [2, 2]
+        troops = 10
+
+        [[empires]]
+        color = [0, 0, 255]
+        start = [9, 9]
+        troops = 10
+        "#,
+    )
+    .unwrap();
+    let world = scenario.build().unwrap();
+    assert_eq!(world.visibility.len(), 2);
+    assert_eq!(visible(&world, 1).len(), 9);
+    assert!(!world.visible(1, 9 * 12 + 9));
+
+    let mut spawned = scenario.clone();
+    spawned.empires.iter_mut().for_each(|empire| {
+        empire.start = None;
+        empire.troops = None;
+    });
+    spawned.spawn = Some(Spawn {
+        strategy: SpawnStrategy::Corners,
+        fair: true,
+    });
+    let spawned = spawned.build().unwrap();
+    assert_eq!(spawned.visibility.len(), 2);
+    assert!(visible(&spawned, 2).len() >= 4);
+
+    let copy = Snapshot::new(&world).to_world();
+    assert_eq!(copy.visibility, world.visibility);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "gpu")]
+use libterritory::{
+    combat::Combat,
+    gpu::GpuBackend,
+    spawn::SpawnStrategy,
+    world::{Topology, World},
+};
+
+mod common;
+
+fn world(topology: Topology) -> World {
+    common::world(topology, 5, SpawnStrategy::EvenlySpaced)
+}
+
+/// These tests need an adapter, a software one like lavapipe or llvmpipe will
+/// do, and fail without one rather than pass without checking anything.
+fn backend() -> GpuBackend {
+    GpuBackend::new().unwrap_or_else(|err| {
+        panic!(
+            "{}, install a software adapter like lavapipe (mesa-vulkan-drivers) to run the GPU tests",
+            err
+        )
+    })
+}
+
+/// Every tick starts from the CPU's state, so rounding differences can't pile up.
+/// GPUs may fuse multiplies and adds, so allow a few cells to come out different.
+/// `wide-troops` worlds always update on the CPU.
+#[cfg(not(feature = "wide-troops"))]
+#[test]
+fn gpu_matches_cpu() {
+    let mut gpu = backend();
+    for topology in [Topology::Torus, Topology::Bounded] {
+        let mut cpu = world(topology);
+        cpu.params.vision = 8;
+        for tick in 0..60 {
+            assert!(gpu.supports(&cpu), "unsupported on tick {}", tick);
+            let mut on_gpu = cpu.clone();
+            gpu.update(&mut on_gpu);
+            cpu.update();
+            assert_eq!(on_gpu.tick, cpu.tick);
+
+            let different = cpu
+                .cells
+                .iter()
+                .zip(on_gpu.cells.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+            assert!(
+                different * 1000 <= cpu.cells.len(),
+                "{} cells differ on tick {} ({:?})",
+                different,
+                tick,
+                topology
+            );
+        }
+    }
+}
+
+#[test]
+fn falls_back_for_empty_worlds() {
+    let mut gpu = backend();
+    let mut world = World::new(0, 0);
+    assert!(!gpu.supports(&world));
+    gpu.update(&mut world);
+    assert_eq!(world.tick, 1);
+}
+
+#[test]
+fn falls_back_for_what_it_cant_do() {
+    let mut gpu = backend();
+    let changes: [fn(&mut World); 6] = [
+        |world| world.empires[0].traits.attack = 1.5,
+        |world| world.params.combat = Combat::Lanchester { exponent: 2.0 },
+        |world| world.params.flow = 0.2,
+        |world| world.params.attrition = 0.1,
+        |world| world.params.tech.per_cell = 1,
+        |world| world.garrisons[7] = 3,
+    ];
+    for (n, change) in changes.iter().enumerate() {
+        let mut cpu = world(Topology::Torus);
+        change(&mut cpu);
+        cpu.params.vision = 8;
+        assert!(!gpu.supports(&cpu), "change {}", n);
+        for tick in 0..20 {
+            let mut on_gpu = cpu.clone();
+            gpu.update(&mut on_gpu);
+            cpu.update();
+            assert!(on_gpu.cells == cpu.cells, "change {} on tick {}", n, tick);
+            assert!(on_gpu.observe(1) == cpu.observe(1));
+        }
+    }
+}
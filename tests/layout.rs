@@ -0,0 +1,47 @@
+use libterritory::{
+    cells::Layout,
+    spawn::SpawnStrategy,
+    world::{Topology, World},
+};
+
+mod common;
+
+fn world(topology: Topology) -> World {
+    common::world(topology, 3, SpawnStrategy::Territories)
+}
+
+#[test]
+fn struct_of_arrays_matches_array_of_structs() {
+    for topology in [Topology::Torus, Topology::Bounded] {
+        let mut aos = world(topology);
+        let mut soa = aos.clone();
+        soa.set_layout(Layout::StructOfArrays);
+        assert_eq!(soa.cells.layout(), Layout::StructOfArrays);
+
+        for tick in 0..100 {
+            aos.update();
+            soa.update();
+            assert!(aos.cells == soa.cells, "diverged on tick {}", tick);
+        }
+    }
+}
+
+#[test]
+fn switching_layout_keeps_cells() {
+    let mut world = world(Topology::Torus);
+    for _ in 0..10 {
+        world.update();
+    }
+    let before = world.clone();
+
+    world.set_layout(Layout::StructOfArrays);
+    world.update();
+    world.set_layout(Layout::ArrayOfStructs);
+    world.update();
+
+    let mut expected = before;
+    expected.update();
+    expected.update();
+    assert!(world.cells == expected.cells);
+    assert_eq!(world.stats(), expected.stats());
+}
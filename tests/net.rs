@@ -0,0 +1,174 @@
+use std::thread;
+
+use libterritory::{
+    net::{Client, NetError, Server, MAX_PAINT_RADIUS},
+    replay::Intervention,
+    spawn::{Spawn, SpawnStrategy},
+    world::{Cell, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+
+fn world() -> World {
+    let mut world = World::new(64, 48);
+    world.seed = 11;
+    world.empires = common::empires(3);
+    world.params.max_troops = 50_000;
+    Spawn {
+        strategy: SpawnStrategy::EvenlySpaced,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(11));
+    world
+}
+
+/// Joins the match, painting now and then, and returns the world after `ticks`.
+fn play(addr: String, ticks: usize) -> Result<World, NetError> {
+    let (mut client, mut world) = Client::join(addr)?;
+    let empire = client.empire().unwrap();
+    while world.tick < ticks {
+        if world.tick % 25 == 5 {
+            client.command(Intervention::Paint {
+                x: world.tick % 64,
+                y: empire as usize * 12,
+                radius: 2,
+                cell: Cell {
+                    owner: empire,
+                    troops: 40_000,
+                },
+            });
+            // Too big a brush, or too many troops, so the server drops them.
+            for (radius, troops) in [(usize::MAX, 1), (MAX_PAINT_RADIUS + 1, 1), (0, 50_001)] {
+                client.command(Intervention::Paint {
+                    x: usize::MAX,
+                    y: 0,
+                    radius,
+                    cell: Cell {
+                        owner: empire,
+                        troops,
+                    },
+                });
+            }
+            // Not this player's empire, so the server drops it.
+            client.command(Intervention::SetColor {
+                id: empire % 3 + 1,
+                color: (0, 0, 0, 255),
+            });
+        }
+        client.step(&mut world)?;
+    }
+    Ok(world)
+}
+
+#[test]
+fn players_stay_in_sync() {
+    let server = Server::bind("127.0.0.1:0", &world(), 3).unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || server.run());
+
+    let players = (0..3)
+        .map(|_| {
+            let addr = addr.clone();
+            thread::spawn(move || play(addr, 150))
+        })
+        .collect::<Vec<_>>();
+    let worlds = players
+        .into_iter()
+        .map(|player| player.join().unwrap().unwrap())
+        .collect::<Vec<_>>();
+    server.join().unwrap().unwrap();
+
+    for world in &worlds[1..] {
+        assert_eq!(world.tick, 150);
+        assert!(world.cells.iter().eq(worlds[0].cells.iter()));
+        assert_eq!(world.empires, worlds[0].empires);
+    }
+    assert!(worlds[0]
+        .empires
+        .iter()
+        .all(|empire| empire.color == (255, 255, 255, 255)));
+
+    // The same match played locally, with only the allowed commands.
+    let mut local = world();
+    while local.tick < 150 {
+        if local.tick % 25 == 5 {
+            for empire in 1..=3 {
+                local.apply(&Intervention::Paint {
+                    x: local.tick % 64,
+                    y: empire as usize * 12,
+                    radius: 2,
+                    cell: Cell {
+                        owner: empire,
+                        troops: 40_000,
+                    },
+                });
+            }
+        }
+        local.update();
+    }
+    assert!(local.cells.iter().eq(worlds[0].cells.iter()));
+}
+
+#[test]
+fn desyncs_are_caught() {
+    let server = Server::bind("127.0.0.1:0", &world(), 2).unwrap();
+    let addr = server.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || server.run());
+
+    let honest = {
+        let addr = addr.clone();
+        thread::spawn(move || play(addr, 100))
+    };
+    let (mut client, mut world) = Client::join(addr).unwrap();
+    let cheater = loop {
+        if world.tick == 20 {
+            world.set(
+                0,
+                0,
+                Cell {
+                    owner: client.empire().unwrap(),
+                    troops: 1,
+                },
+            );
+        }
+        if let Err(e) = client.step(&mut world) {
+            break e;
+        }
+    };
+
+    assert!(matches!(cheater, NetError::Desync { tick: 20 }));
+    assert!(matches!(
+        honest.join().unwrap(),
+        Err(NetError::Desync { tick: 20 })
+    ));
+    assert!(matches!(
+        server.join().unwrap(),
+        Err(NetError::Desync { tick: 20 })
+    ));
+}
+
+#[test]
+fn needs_an_empire_per_player() {
+    assert!(Server::bind("127.0.0.1:0", &world(), 4).is_err());
+    assert!(Server::bind("127.0.0.1:0", &world(), 0).is_err());
+}
+
+#[test]
+fn huge_brushes_stay_in_the_world() {
+    let mut world = world();
+    world.apply(&Intervention::Paint {
+        x: usize::MAX,
+        y: usize::MAX,
+        radius: usize::MAX,
+        cell: Cell {
+            owner: 1,
+            troops: 7,
+        },
+    });
+    assert!(world.cells.iter().all(|cell| cell
+        == Cell {
+            owner: 1,
+            troops: 7
+        }));
+}
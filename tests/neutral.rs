@@ -0,0 +1,238 @@
+use libterritory::{
+    cells::Layout,
+    combat::Combat,
+    neutral::GarrisonSource,
+    replay::{Intervention, Player, Replay},
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    terrain::{Terrain, TerrainGenerator},
+    world::{Cell, Topology, Troops, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+use common::empires;
+
+/// A row of wild cells holding `garrison` troops, with empire 1 on the left.
+fn frontier(troops: Troops, garrison: Troops) -> World {
+    let mut world = World::new(5, 1);
+    world.seed = 3;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.takeover_min = 1.0;
+    world.params.takeover_max = 1.0;
+    world.empires = empires(1);
+    world.apply(&Intervention::Garrisons(vec![garrison; 5]));
+    world.set(0, 0, Cell { owner: 1, troops });
+    world
+}
+
+fn row(world: &World) -> Vec<Cell> {
+    world.cells.iter().collect()
+}
+
+#[test]
+fn the_wild_costs_troops() {
+    let mut world = frontier(1000, 600);
+    world.update();
+    let wild = Cell {
+        owner: 0,
+        troops: 600,
+    };
+    assert_eq!(
+        row(&world),
+        [
+            Cell {
+                owner: 1,
+                troops: 1000
+            },
+            Cell {
+                owner: 1,
+                troops: 400
+            },
+            wild,
+            wild,
+            wild
+        ]
+    );
+
+    // Garrisons never attack, however strong.
+    let mut world = frontier(100, 5000);
+    world.update();
+    assert_eq!(
+        row(&world)[0],
+        Cell {
+            owner: 1,
+            troops: 100
+        }
+    );
+    assert_eq!(row(&world)[1].owner, 0);
+}
+
+#[test]
+fn garrisons_grow_back() {
+    let mut world = frontier(0, 100);
+    world.set(3, 0, Cell::default());
+    world.params.regrowth = 0.5;
+    world.update();
+    assert_eq!(world.get(3, 0).unwrap().troops, 50);
+    world.update();
+    assert_eq!(world.get(3, 0).unwrap().troops, 75);
+    for _ in 0..20 {
+        world.update();
+    }
+    assert_eq!(world.get(3, 0).unwrap().troops, 100);
+
+    let mut world = frontier(0, 100);
+    world.set(3, 0, Cell::default());
+    world.update();
+    assert_eq!(world.get(3, 0).unwrap().troops, 0);
+}
+
+#[test]
+fn garrisons_follow_terrain_or_noise() {
+    let terrain = [
+        Terrain::Plains,
+        Terrain::Hills,
+        Terrain::Mountains,
+        Terrain::Water,
+    ];
+    assert_eq!(
+        GarrisonSource::Terrain { troops: 100 }.generate(&terrain, 4, 1),
+        [100, 150, 200, 0]
+    );
+
+    let terrain = vec![Terrain::Plains; 64 * 64];
+    let noise = GarrisonSource::Noise {
+        troops: 1000,
+        seed: 1,
+        scale: 16.0,
+    }
+    .generate(&terrain, 64, 64);
+    assert!(noise.iter().all(|&troops| troops <= 1000));
+    assert!(noise.iter().any(|&troops| troops < 100));
+    assert!(noise.iter().any(|&troops| troops > 900));
+}
+
+fn wilderness() -> World {
+    let mut world = World::new(80, 60);
+    world.seed = 10;
+    world.params.regrowth = 0.1;
+    world.params.combat = Combat::Dice { sides: 6 };
+    world.terrain = TerrainGenerator {
+        seed: 10,
+        scale: 20.0,
+        ..Default::default()
+    }
+    .generate(80, 60);
+    world.garrisons = GarrisonSource::Noise {
+        troops: 20_000,
+        seed: 10,
+        scale: 10.0,
+    }
+    .generate(&world.terrain, 80, 60);
+    world.empires = empires(3);
+    Spawn {
+        strategy: SpawnStrategy::EvenlySpaced,
+        fair: true,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(10));
+    world
+}
+
+#[test]
+fn spawning_keeps_the_garrisons() {
+    let world = wilderness();
+    for (i, cell) in world.cells.iter().enumerate() {
+        if cell.owner == 0 {
+            let expected = if world.terrain[i].claimable() {
+                world.garrisons[i]
+            } else {
+                0
+            };
+            assert_eq!(cell.troops, expected);
+        }
+    }
+}
+
+#[test]
+fn the_wild_is_deterministic() {
+    let mut world = wilderness();
+    world.check_parallel = true;
+    let mut full = world.clone();
+    let mut soa = world.clone();
+    soa.set_layout(Layout::StructOfArrays);
+    for tick in 0..40 {
+        world.update();
+        full.update_full();
+        soa.update();
+        assert!(world.cells == full.cells, "tick {}", tick);
+        assert!(world.cells.iter().eq(soa.cells.iter()), "tick {}", tick);
+    }
+    assert_eq!(world.parallel_mismatch, None);
+}
+
+#[test]
+fn garrisons_replay() {
+    let mut world = wilderness();
+    let mut replay = Replay::new(&world);
+    for _ in 0..30 {
+        world.update();
+    }
+    let garrisons = GarrisonSource::Terrain { troops: 500 }.generate(&world.terrain, 80, 60);
+    replay.record(&mut world, Intervention::Garrisons(garrisons));
+    for _ in 0..30 {
+        world.update();
+    }
+    replay.end = world.tick;
+
+    let mut player = Player::new(Replay::from_bytes(&replay.to_bytes().unwrap()).unwrap());
+    player.seek(usize::MAX);
+    assert_eq!(player.world().garrisons, world.garrisons);
+    assert!(player.world().cells.iter().eq(world.cells.iter()));
+}
+
+#[test]
+fn garrisons_are_set_in_scenarios() {
+    let world = Scenario::from_toml(
+        r#"
+        width = 4
+        height = 1
+
+        [terrain]
+        type = "ascii"
+        rows = [".n^~"]
+
+        [garrisons]
+        type = "terrain"
+        troops = 10
+
+        [[empires]]
+        color = [255, 0, 0]
+        start = [0, 0]
+        troops = 100
+        "#,
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(world.garrisons, [10, 15, 20, 0]);
+    assert_eq!(
+        row(&world),
+        [
+            Cell {
+                owner: 1,
+                troops: 100
+            },
+            Cell {
+                owner: 0,
+                troops: 15
+            },
+            Cell {
+                owner: 0,
+                troops: 20
+            },
+            Cell::default()
+        ]
+    );
+}
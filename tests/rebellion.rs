@@ -0,0 +1,170 @@
+use libterritory::{
+    rebellion::Rebellion,
+    world::{Cell, Empire, Topology, World},
+};
+
+fn empire(id: u16, color: (u8, u8, u8, u8)) -> Empire {
+    Empire::new(id, format!("Empire {}", id), color)
+}
+
+/// Between two colors in RGB.
+fn distance(a: (u8, u8, u8, u8), b: (u8, u8, u8, u8)) -> f32 {
+    let [a, b] = [a, b].map(|(r, g, b, _)| [r, g, b].map(|c| c as f32));
+    (0..3).map(|c| (a[c] - b[c]).powi(2)).sum::<f32>().sqrt()
+}
+
+/// Empire 1 on the left two columns and a pocket in the rightmost one, empire 2
+/// in between, with the same troops everywhere so that the rules alone change
+/// nothing.
+fn split() -> World {
+    let mut world = World::new(7, 3);
+    world.seed = 5;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.rebellion.interval = 1;
+    world.empires = vec![empire(1, (200, 50, 0, 255)), empire(2, (0, 0, 200, 255))];
+    for y in 0..3 {
+        for x in 0..7 {
+            let owner = if (2..6).contains(&x) { 2 } else { 1 };
+            world.set(
+                x,
+                y,
+                Cell {
+                    owner,
+                    troops: 1000,
+                },
+            );
+        }
+    }
+    world
+}
+
+#[test]
+fn pockets_rebel() {
+    let mut world = split();
+    world.params.rebellion.pocket_cells = 3;
+    world.update();
+
+    assert_eq!(world.empires.len(), 3);
+    let rebels = &world.empires[2];
+    assert_eq!(*rebels, Empire::new(3, "Empire 1 Rebels", rebels.color));
+    // The parent's color turned around the hue wheel, as bright as before.
+    let (r, g, b, a) = rebels.color;
+    assert_eq!((r.max(g).max(b), a), (200, 255));
+    for empire in &world.empires[..2] {
+        assert!(distance(rebels.color, empire.color) >= 64.0);
+    }
+    assert_eq!(
+        world.rebellions,
+        [Rebellion {
+            tick: 1,
+            empire: 1,
+            rebels: 3,
+            cells: 3,
+        }]
+    );
+    for y in 0..3 {
+        assert_eq!(
+            world.get(6, y),
+            Some(Cell {
+                owner: 3,
+                troops: 1000
+            })
+        );
+        assert_eq!(world.get(0, y).unwrap().owner, 1);
+    }
+
+    // The rebels are whole, so nothing happens again.
+    world.update();
+    assert_eq!(world.empires.len(), 3);
+}
+
+#[test]
+fn small_pockets_and_quiet_ticks_stay_loyal() {
+    let mut world = split();
+    world.params.rebellion.pocket_cells = 4;
+    world.update();
+    assert_eq!(world.empires.len(), 2);
+
+    let mut world = split();
+    world.params.rebellion.pocket_cells = 3;
+    world.params.rebellion.interval = 2;
+    world.update();
+    assert_eq!(world.empires.len(), 2);
+    world.update();
+    assert_eq!(world.empires.len(), 3);
+    assert_eq!(world.rebellions[0].tick, 2);
+}
+
+#[test]
+fn big_empires_break_up() {
+    let over_extended = || {
+        let mut world = World::new(10, 10);
+        world.seed = 12;
+        world.params.decay = 1.0;
+        world.params.rebellion.interval = 1;
+        world.params.rebellion.max_cells = 50;
+        world.params.rebellion.breakaway = 0.3;
+        world.empires = vec![empire(1, (128, 128, 128, 255))];
+        world.cells.fill(Cell {
+            owner: 1,
+            troops: 1000,
+        });
+        world
+    };
+
+    let mut world = over_extended();
+    world.update();
+    assert_eq!(world.empires.len(), 2);
+    // Gray has no hue, so the rebels get one.
+    let (r, g, b, _) = world.empires[1].color;
+    assert!(r != g || g != b);
+    assert!(distance(world.empires[1].color, world.empires[0].color) >= 64.0);
+    let stats = world.stats();
+    assert_eq!((stats[0].cells, stats[1].cells), (70, 30));
+    // The rebels hang together.
+    let components = world.components();
+    assert_eq!(components.components.len(), 2);
+
+    let mut again = over_extended();
+    again.update();
+    assert!(world.cells == again.cells);
+    assert_eq!(world.empires, again.empires);
+
+    // Every group of rebels gets a color far from the others', however many
+    // there are, rebels of rebels included.
+    world.params.rebellion.max_cells = 10;
+    for _ in 0..5 {
+        world.update();
+    }
+    assert!(world.empires.len() >= 6);
+    assert!(world
+        .rebellions
+        .iter()
+        .any(|rebellion| rebellion.empire > 2));
+    for (n, empire) in world.empires.iter().enumerate() {
+        assert!(world.empires[..n]
+            .iter()
+            .all(|other| distance(other.color, empire.color) >= 64.0));
+    }
+}
+
+#[test]
+fn cells_without_an_empire_dont_rebel() {
+    let mut world = split();
+    world.params.rebellion.pocket_cells = 3;
+    world.empires.truncate(1);
+    world.update();
+
+    // Empire 1's pocket still rebels, as the next id.
+    assert_eq!(world.empires.len(), 2);
+    assert_eq!(
+        world.rebellions,
+        [Rebellion {
+            tick: 1,
+            empire: 1,
+            rebels: 2,
+            cells: 3,
+        }]
+    );
+}
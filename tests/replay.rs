@@ -0,0 +1,178 @@
+use libterritory::{
+    rebellion::Rebellion,
+    replay::{Intervention, Player, Replay, ReplayError, Snapshot},
+    spawn::{Spawn, SpawnStrategy},
+    terrain::TerrainGenerator,
+    world::{Cell, Topology, VictoryCondition, World},
+};
+
+fn world() -> World {
+    let mut world = World::new(80, 60);
+    world.seed = 7;
+    world.topology = Topology::Bounded;
+    world.victory = vec![
+        VictoryCondition::LastStanding,
+        VictoryCondition::Territory { share: 0.9 },
+    ];
+    world
+}
+
+/// Plays a match with interventions along the way, recording it.
+fn record(ticks: usize) -> (Replay, World) {
+    let mut world = world();
+    let mut replay = Replay::new(&world);
+    for n in 1..=3 {
+        replay.record(
+            &mut world,
+            Intervention::AddEmpire {
+                name: format!("Empire {}", n),
+                color: (n * 80, 0, 0, 255),
+                traits: Default::default(),
+            },
+        );
+    }
+    let terrain = TerrainGenerator {
+        seed: 7,
+        scale: 20.0,
+        ..Default::default()
+    }
+    .generate(80, 60);
+    replay.record(&mut world, Intervention::Terrain(terrain));
+    replay.record(
+        &mut world,
+        Intervention::Spawn {
+            spawn: Spawn {
+                strategy: SpawnStrategy::EvenlySpaced,
+                fair: false,
+            },
+            seed: 7,
+        },
+    );
+
+    while world.tick < ticks {
+        world.update();
+        if world.tick % 97 == 50 {
+            let paint = Intervention::Paint {
+                x: world.tick % 80,
+                y: world.tick % 60,
+                radius: 3,
+                cell: Cell {
+                    owner: (world.tick / 97 % 3 + 1) as u16,
+                    troops: 30_000,
+                },
+            };
+            replay.record(&mut world, paint);
+        }
+        if world.tick == 250 {
+            replay.record(
+                &mut world,
+                Intervention::SetColor {
+                    id: 2,
+                    color: (0, 255, 0, 255),
+                },
+            );
+        }
+    }
+    replay.end = world.tick;
+    (replay, world)
+}
+
+fn assert_same(a: &World, b: &World) {
+    assert_eq!(a.tick, b.tick);
+    assert_eq!(a.empires, b.empires);
+    assert_eq!(a.rebellions, b.rebellions);
+    assert_eq!(a.terrain, b.terrain);
+    assert!(
+        a.cells.iter().eq(b.cells.iter()),
+        "cells differ at tick {}",
+        a.tick
+    );
+}
+
+#[test]
+fn playback_matches_recording() {
+    let (replay, world) = record(600);
+    let mut player = Player::new(replay);
+    while !player.finished() {
+        player.step();
+    }
+    assert_same(player.world(), &world);
+}
+
+#[test]
+fn seeking_back_matches_playing_forward() {
+    let (replay, _) = record(600);
+    let mut forward = Player::new(replay.clone());
+    forward.seek(500);
+    let at_500 = forward.world().clone();
+    forward.seek(300);
+    let at_300 = forward.world().clone();
+
+    let mut player = Player::new(replay);
+    player.seek(300);
+    assert_same(player.world(), &at_300);
+    player.seek(500);
+    assert_same(player.world(), &at_500);
+
+    player.seek(usize::MAX);
+    assert_eq!(player.tick(), 600);
+    player.seek(0);
+    assert_eq!(player.world().empires.len(), 3);
+}
+
+#[test]
+fn round_trips_through_bytes() {
+    let (replay, _) = record(300);
+    let bytes = replay.to_bytes().unwrap();
+    assert_eq!(Replay::from_bytes(&bytes).unwrap(), replay);
+
+    assert!(matches!(
+        Replay::from_bytes(b"not a replay"),
+        Err(ReplayError::NotAReplay)
+    ));
+}
+
+#[test]
+fn broken_snapshots_are_rejected() {
+    // Three empires by now.
+    let (_, world) = record(10);
+    let replay = Replay::new(&world);
+    let breakages: [fn(&mut Snapshot); 5] = [
+        |snapshot| {
+            snapshot.troops.pop();
+        },
+        |snapshot| snapshot.garrisons.clear(),
+        |snapshot| snapshot.width += 1,
+        |snapshot| (snapshot.width, snapshot.height) = (usize::MAX, 2),
+        |snapshot| snapshot.owners[0] = 4,
+    ];
+    for breakage in breakages {
+        let mut broken = replay.clone();
+        breakage(&mut broken.initial);
+        let bytes = broken.to_bytes().unwrap();
+        assert!(matches!(
+            Replay::from_bytes(&bytes),
+            Err(ReplayError::Invalid(_))
+        ));
+    }
+
+    // The last empire's cells are fine.
+    let mut replay = replay;
+    replay.initial.owners[0] = 3;
+    let bytes = replay.to_bytes().unwrap();
+    assert!(Replay::from_bytes(&bytes).is_ok());
+}
+
+#[test]
+fn snapshots_keep_the_rebellions() {
+    let (_, mut world) = record(10);
+    world.rebellions.push(Rebellion {
+        tick: 5,
+        empire: 2,
+        rebels: 4,
+        cells: 12,
+    });
+    let copy = Snapshot::new(&world).to_world();
+    assert_same(&copy, &world);
+    assert_eq!(copy.rebellions.len(), 1);
+}
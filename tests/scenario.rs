@@ -0,0 +1,179 @@
+use std::fs;
+
+use libterritory::{
+    scenario::{Scenario, ScenarioError},
+    sweep::Sweep,
+    world::{Cell, World},
+};
+
+/// A 16x16 scenario with `extra` after its size, and one empire.
+fn scenario(extra: &str, empire: &str) -> Result<World, ScenarioError> {
+    Scenario::from_toml(&format!(
+        "width = 16\nheight = 16\n{}\n[[empires]]\ncolor = [255, 0, 0]\n{}",
+        extra, empire
+    ))
+    .unwrap()
+    .build()
+}
+
+fn invalid(result: Result<World, ScenarioError>) -> String {
+    match result {
+        Err(ScenarioError::Invalid(message)) => message,
+        Err(err) => panic!("expected an invalid scenario, got {}", err),
+        Ok(_) => panic!("expected an invalid scenario"),
+    }
+}
+
+const START: &str = "start = [3, 4]\ntroops = 500";
+
+#[test]
+fn scenarios_place_their_empires() {
+    let world = scenario("", START).unwrap();
+    assert_eq!(
+        world.get(3, 4),
+        Some(Cell {
+            owner: 1,
+            troops: 500
+        })
+    );
+    assert_eq!(world.cells.iter().filter(|cell| cell.owner != 0).count(), 1);
+
+    let message = invalid(scenario("", "start = [3, 4]"));
+    assert!(message.contains("needs a start and troops"), "{}", message);
+    let message = invalid(scenario("", "start = [16, 0]\ntroops = 500"));
+    assert!(message.contains("outside"), "{}", message);
+}
+
+#[test]
+fn fractions_must_be_fractions() {
+    for params in [
+        "decay = 1.5",
+        "decay = -0.1",
+        "decay = nan",
+        "flow = 2.0",
+        "attrition = -1.0",
+        "regrowth = 1.01",
+        "rebellion = { breakaway = 3.0 }",
+        "events = [{ duration = 1, event = { type = \"plague\", density = 1, loss = 2.0 } }]",
+    ] {
+        let message = invalid(scenario(&format!("[params]\n{}", params), START));
+        assert!(
+            message.contains("between 0 and 1"),
+            "{}: {}",
+            params,
+            message
+        );
+    }
+    assert!(scenario("[params]\ndecay = 1.0\nflow = 0.0", START).is_ok());
+
+    let message = invalid(scenario(
+        "[[victory]]\ntype = \"territory\"\nshare = 1.5",
+        START,
+    ));
+    assert!(message.contains("victory share"), "{}", message);
+}
+
+#[test]
+fn takeover_range_must_be_ordered() {
+    let message = invalid(scenario(
+        "[params]\ntakeover_min = 1.2\ntakeover_max = 0.9",
+        START,
+    ));
+    assert!(message.contains("takeover_min"), "{}", message);
+    assert!(invalid(scenario("[params]\ntakeover_min = -0.5", START)).contains("takeover_min"));
+    assert!(scenario("[params]\ntakeover_min = 1.0\ntakeover_max = 1.0", START).is_ok());
+}
+
+#[test]
+fn spawn_tables_place_everyone() {
+    let spawn = "[spawn]\nstrategy = \"circle\"\n";
+    let world = scenario(spawn, "").unwrap();
+    assert_eq!(world.cells.iter().filter(|cell| cell.owner == 1).count(), 1);
+
+    // Starts that the spawn would ignore are a mistake.
+    for empire in [START, "start = [3, 4]", "troops = 500"] {
+        let message = invalid(scenario(spawn, empire));
+        assert!(message.contains("[spawn]"), "{}", message);
+    }
+}
+
+#[test]
+fn worlds_cant_be_empty() {
+    for size in ["width = 0\nheight = 16", "width = 16\nheight = 0"] {
+        let scenario = Scenario::from_toml(&format!("{}\n[[empires]]\ncolor = [1, 2, 3]", size));
+        match scenario.unwrap().build() {
+            Err(ScenarioError::Invalid(message)) => assert!(message.contains("at least 1x1")),
+            _ => panic!("{} built", size),
+        }
+    }
+}
+
+#[test]
+fn traits_must_be_usable() {
+    for (extra, empire) in [
+        ("", "traits = { attack = 0.0 }"),
+        ("", "traits = { attack = inf }"),
+        ("", "traits = { defense = -1.0 }"),
+        ("", "traits = { affinities = { hills = nan } }"),
+        (
+            "[params.tech]\ntiers = [{ research = 10, traits = { growth = -0.5 } }]",
+            "",
+        ),
+        (
+            "[params]\nevents = [{ duration = 1, event = { type = \"winter\", decay = -2.0 } }]",
+            "",
+        ),
+        (
+            "[params]\nevents = [{ duration = 1, event = { type = \"harvest\", growth = inf } }]",
+            "",
+        ),
+    ] {
+        let message = invalid(scenario(extra, &format!("{}\n{}", START, empire)));
+        assert!(
+            message.contains("more than 0") || message.contains("at least 0"),
+            "{} {}: {}",
+            extra,
+            empire,
+            message
+        );
+    }
+    assert!(scenario("", &format!("{}\ntraits = {{ decay = 0.0 }}", START)).is_ok());
+}
+
+#[test]
+fn misspelled_keys_are_rejected() {
+    for source in [
+        "width = 16\nheight = 16\nwidht = 3",
+        "width = 16\nheight = 16\n[params]\ndecya = 0.5",
+        "width = 16\nheight = 16\n[params.rebellion]\nintreval = 5",
+        "width = 16\nheight = 16\n[[empires]]\ncolor = [1, 2, 3]\ntroop = 5",
+        "width = 16\nheight = 16\n[[empires]]\ncolor = [1, 2, 3]\ntraits = { atack = 2.0 }",
+        "width = 16\nheight = 16\n[terrain]\ntype = \"noise\"\nscael = 3.0",
+        "width = 16\nheight = 16\n[params.combat]\ntype = \"dice\"\nsides = 6\nside = 6",
+        "width = 16\nheight = 16\n[spawn]\nstrategy = \"grid\"\nfiar = true",
+    ] {
+        assert!(
+            matches!(Scenario::from_toml(source), Err(ScenarioError::Parse(_))),
+            "{}",
+            source
+        );
+    }
+    assert!(matches!(
+        Sweep::from_toml("width = 16\nheight = 16\n[sweep]\nrusn = 3"),
+        Err(ScenarioError::Parse(_))
+    ));
+}
+
+#[test]
+fn bundled_scenarios_build() {
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/scenarios")).unwrap() {
+        let path = entry.unwrap().path();
+        let sweep = Sweep::load(&path).unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+        // Sweeps can leave placing the empires to their spawns.
+        let mut scenario = sweep.scenario;
+        scenario.spawn = scenario.spawn.or(sweep.grid.spawns.first().copied());
+        scenario
+            .build()
+            .unwrap_or_else(|e| panic!("{:?}: {}", path, e));
+    }
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "scripting")]
+use std::sync::Arc;
+
+use libterritory::{
+    script::{ScriptError, ScriptRule},
+    spawn::{Spawn, SpawnStrategy},
+    world::{Cell, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+
+fn world() -> World {
+    let mut world = World::new(12, 10);
+    world.seed = 13;
+    world.empires = common::empires(3);
+    Spawn {
+        strategy: SpawnStrategy::EvenlySpaced,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(13));
+    world
+}
+
+/// `world` running `source` next to one without a script, for `ticks`.
+fn scripted(source: &str, ticks: usize) -> (World, World, Arc<ScriptRule>) {
+    let script = Arc::new(ScriptRule::new(source).unwrap());
+    let mut builtin = world();
+    let mut world = builtin.clone();
+    world.script = Some(script.clone());
+    for _ in 0..ticks {
+        world.update();
+        builtin.update();
+    }
+    (world, builtin, script)
+}
+
+#[test]
+fn scripts_replace_the_rules() {
+    let (world, builtin, script) = scripted(
+        "fn update(cell, neighbors, rng) { #{ owner: 1, troops: TICK + 5 } }",
+        3,
+    );
+    assert_eq!(script.error(), None);
+    assert!(world.cells.iter().all(|cell| cell
+        == Cell {
+            owner: 1,
+            troops: 7
+        }));
+    assert!(world.cells != builtin.cells);
+}
+
+#[test]
+fn the_default_script_runs() {
+    let (world, _, script) = scripted(include_str!("../scripts/default.rhai"), 5);
+    assert_eq!(script.error(), None);
+    assert!(world.cells.iter().any(|cell| cell.owner != 0));
+}
+
+#[test]
+fn failing_cells_keep_the_builtin_rules() {
+    for source in [
+        "fn update(cell, neighbors, rng) { throw \"no\"; }",
+        "fn update(cell, neighbors, rng) { #{ owner: EMPIRES + 1, troops: 1 } }",
+        "fn update(cell, neighbors, rng) { #{ owner: 1 } }",
+        "fn update(cell, neighbors, rng) { 42 }",
+    ] {
+        let (world, builtin, script) = scripted(source, 10);
+        assert!(script.error().is_some(), "{}", source);
+        assert!(world.cells == builtin.cells, "{}", source);
+    }
+}
+
+#[test]
+fn runaway_scripts_are_stopped() {
+    let (world, builtin, script) = scripted(
+        "fn update(cell, neighbors, rng) { loop { cell.troops += 1; } }",
+        2,
+    );
+    let error = script.error().unwrap();
+    assert!(error.contains("operations"), "{}", error);
+    assert!(world.cells == builtin.cells);
+}
+
+#[test]
+fn scripts_need_an_update() {
+    for source in ["fn update(cell) { cell }", "fn update(", ""] {
+        assert!(matches!(
+            ScriptRule::new(source),
+            Err(ScriptError::Compile(_))
+        ));
+    }
+}
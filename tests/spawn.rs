@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+
+use libterritory::{
+    spawn::{Spawn, SpawnStrategy},
+    terrain::{Terrain, TerrainGenerator},
+    world::{Topology, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+
+fn world(width: usize, height: usize, empires: u16) -> World {
+    let mut world = World::new(width, height);
+    world.seed = 12;
+    world.empires = common::empires(empires);
+    world
+}
+
+fn spawn(world: &mut World, strategy: SpawnStrategy, fair: bool, seed: u64) {
+    Spawn { strategy, fair }.apply(world, &mut StdRng::seed_from_u64(seed));
+}
+
+/// The ids of the empires that own a cell, each once per cell.
+fn owners(world: &World) -> Vec<u16> {
+    world
+        .cells
+        .iter()
+        .filter(|cell| cell.owner != 0)
+        .map(|cell| cell.owner)
+        .collect()
+}
+
+#[test]
+fn every_empire_gets_a_cell_of_its_own() {
+    for strategy in SpawnStrategy::ALL {
+        if strategy == SpawnStrategy::Territories {
+            continue;
+        }
+        for seed in 0..5 {
+            let mut world = world(64, 48, 6);
+            spawn(&mut world, strategy, false, seed);
+            let mut owners = owners(&world);
+            owners.sort();
+            assert_eq!(owners, [1, 2, 3, 4, 5, 6], "{:?}", strategy);
+            assert!(world
+                .cells
+                .iter()
+                .all(|cell| cell.owner != 0 || cell.troops == 0));
+        }
+    }
+}
+
+#[test]
+fn crowded_spawns_dont_overlap() {
+    // More empires than the grid has blocks and the circle has cells.
+    for strategy in SpawnStrategy::ALL {
+        let mut world = world(4, 3, 10);
+        world.topology = Topology::Bounded;
+        spawn(&mut world, strategy, false, 1);
+        let owners = owners(&world);
+        assert_eq!(
+            owners.iter().collect::<HashSet<_>>().len(),
+            10,
+            "{:?}",
+            strategy
+        );
+        if strategy != SpawnStrategy::Territories {
+            assert_eq!(owners.len(), 10, "{:?}", strategy);
+        }
+    }
+
+    // Empires that can't all fit get nothing rather than someone else's cell.
+    let mut world = world(3, 1, 5);
+    world.terrain[1] = Terrain::Water;
+    spawn(&mut world, SpawnStrategy::Grid, false, 1);
+    assert_eq!(owners(&world).len(), 2);
+}
+
+#[test]
+fn spawns_avoid_unclaimable_terrain() {
+    for strategy in SpawnStrategy::ALL {
+        let mut world = world(96, 64, 5);
+        world.terrain = TerrainGenerator {
+            seed: 12,
+            scale: 20.0,
+            ..Default::default()
+        }
+        .generate(96, 64);
+        spawn(&mut world, strategy, false, 3);
+        for (i, cell) in world.cells.iter().enumerate() {
+            assert!(cell.owner == 0 || world.terrain[i].claimable());
+        }
+        assert_eq!(owners(&world).iter().collect::<HashSet<_>>().len(), 5);
+    }
+}
+
+#[test]
+fn territories_cover_the_world() {
+    let mut world = world(50, 40, 4);
+    spawn(&mut world, SpawnStrategy::Territories, false, 7);
+    let owners = owners(&world);
+    assert_eq!(owners.len(), 50 * 40);
+    for id in 1..=4 {
+        assert!(owners.contains(&id));
+    }
+}
+
+#[test]
+fn everyone_starts_with_troops() {
+    for seed in 0..20 {
+        let mut world = world(32, 32, 8);
+        world.params.max_troops = 3;
+        spawn(&mut world, SpawnStrategy::Random, false, seed);
+        for cell in world.cells.iter().filter(|cell| cell.owner != 0) {
+            assert!((1..=3).contains(&cell.troops), "{:?}", cell);
+        }
+    }
+
+    // With nothing allowed, there's nothing to start with.
+    let mut world = world(8, 8, 2);
+    world.params.max_troops = 0;
+    spawn(&mut world, SpawnStrategy::Grid, true, 0);
+    assert_eq!(owners(&world).len(), 2);
+}
+
+#[test]
+fn fair_spawns_start_even() {
+    for strategy in SpawnStrategy::ALL {
+        let mut world = world(64, 48, 6);
+        spawn(&mut world, strategy, true, 5);
+        let troops = world
+            .cells
+            .iter()
+            .filter(|cell| cell.owner != 0)
+            .map(|cell| cell.troops)
+            .collect::<HashSet<_>>();
+        assert_eq!(troops.len(), 1, "{:?}", strategy);
+
+        let mut unfair = world.clone();
+        spawn(&mut unfair, strategy, false, 5);
+        let troops = (1..=6)
+            .map(|id| {
+                unfair
+                    .cells
+                    .iter()
+                    .find(|cell| cell.owner == id)
+                    .unwrap()
+                    .troops
+            })
+            .collect::<HashSet<_>>();
+        assert!(troops.len() > 1, "{:?}", strategy);
+    }
+}
+
+#[test]
+fn spawns_are_deterministic() {
+    for strategy in SpawnStrategy::ALL {
+        let mut a = world(40, 30, 5);
+        let mut b = a.clone();
+        spawn(&mut a, strategy, false, 9);
+        spawn(&mut b, strategy, false, 9);
+        assert!(a.cells == b.cells, "{:?}", strategy);
+    }
+}
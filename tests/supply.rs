@@ -0,0 +1,108 @@
+use libterritory::{
+    supply::{Component, NO_COMPONENT},
+    world::{Cell, Empire, Topology, World},
+};
+
+/// Empire 1 on the left two columns and the rightmost one, empire 2 in
+/// between, with the same troops everywhere so that the rules alone change
+/// nothing.
+fn split(topology: Topology) -> World {
+    let mut world = World::new(7, 3);
+    world.seed = 5;
+    world.topology = topology;
+    world.params.decay = 1.0;
+    world.empires = (1..=2)
+        .map(|id| Empire::new(id, format!("Empire {}", id), (200, 200, 200, 255)))
+        .collect();
+    for y in 0..3 {
+        for x in 0..7 {
+            let owner = if (2..6).contains(&x) { 2 } else { 1 };
+            world.set(
+                x,
+                y,
+                Cell {
+                    owner,
+                    troops: 1000,
+                },
+            );
+        }
+    }
+    world
+}
+
+fn pocket(i: usize) -> bool {
+    i % 7 == 6
+}
+
+#[test]
+fn pockets_are_found() {
+    let world = split(Topology::Bounded);
+    let components = world.components();
+    assert_eq!(
+        components.components,
+        [
+            Component {
+                owner: 1,
+                cells: 6,
+                main: true
+            },
+            Component {
+                owner: 2,
+                cells: 12,
+                main: true
+            },
+            Component {
+                owner: 1,
+                cells: 3,
+                main: false
+            },
+        ]
+    );
+    for i in 0..21 {
+        assert_eq!(components.cut_off(i), pocket(i), "cell {}", i);
+    }
+
+    let mut world = world;
+    world.set(3, 1, Cell::default());
+    let components = world.components();
+    assert_eq!(components.labels[10], NO_COMPONENT);
+    assert!(!components.cut_off(10));
+}
+
+#[test]
+fn wrapping_around_reconnects() {
+    let components = split(Topology::Torus).components();
+    assert_eq!(components.components.len(), 2);
+    assert!((0..21).all(|i| !components.cut_off(i)));
+}
+
+#[test]
+fn pockets_waste_away() {
+    let mut world = split(Topology::Bounded);
+    world.params.attrition = 0.25;
+    world.update();
+    for i in 0..21 {
+        let expected = if pocket(i) { 750 } else { 1000 };
+        assert_eq!(world.cells.get(i).troops, expected, "cell {}", i);
+    }
+
+    let mut world = split(Topology::Bounded);
+    world.update();
+    assert!(world.cells.iter().all(|cell| cell.troops == 1000));
+}
+
+#[test]
+fn overlay_only_touches_pockets() {
+    let world = split(Topology::Bounded);
+    let mut frame = vec![0; 21 * 4];
+    world.draw(&mut frame);
+    let mut overlaid = frame.clone();
+    world.draw_cut_off(&mut overlaid);
+    for i in 0..21 {
+        let pixel = i * 4..i * 4 + 4;
+        if !pocket(i) {
+            assert_eq!(frame[pixel.clone()], overlaid[pixel]);
+        }
+    }
+    assert_ne!(frame, overlaid);
+}
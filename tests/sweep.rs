@@ -0,0 +1,294 @@
+use libterritory::{
+    combat::Combat,
+    scenario::ScenarioError,
+    spawn::{Spawn, SpawnStrategy},
+    sweep::{Match, Sweep},
+};
+
+/// A sweep of a 16x16 scenario with `extra` after its size.
+fn sweep(extra: &str) -> Sweep {
+    Sweep::from_toml(&format!("width = 16\nheight = 16\n{}", extra)).unwrap()
+}
+
+const RED: &str = "[[empires]]\nname = \"Red\"\ncolor = [255, 0, 0]\n";
+
+#[test]
+fn grids_cover_every_combination() {
+    let sweep = sweep(&format!(
+        r#"
+        {}
+        [sweep]
+        runs = 3
+        params = [{{ decay = 0.9 }}, {{ decay = 0.95 }}]
+        spawns = [{{ strategy = "grid" }}, {{ strategy = "corners", fair = true }}, {{ strategy = "circle" }}]
+        "#,
+        RED
+    ));
+    let configs = sweep.configs().unwrap();
+    assert_eq!(configs.len(), 6);
+    assert_eq!(configs[0].params.decay, 0.9);
+    assert_eq!(configs[5].params.decay, 0.95);
+    assert_eq!(
+        configs[1].spawn,
+        Some(Spawn {
+            strategy: SpawnStrategy::Corners,
+            fair: true
+        })
+    );
+
+    let matches = sweep.matches();
+    assert_eq!(matches.len(), 18);
+    assert_eq!(matches[0], Match { config: 0, seed: 0 });
+    assert_eq!(matches[17], Match { config: 5, seed: 2 });
+
+    // Without a grid, the scenario's own params and starts are played.
+    let sweep = self::sweep(&format!(
+        "{}start = [1, 1]\ntroops = 10\n[params]\ndecay = 0.5\n",
+        RED
+    ));
+    let configs = sweep.configs().unwrap();
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].params.decay, 0.5);
+    assert_eq!(configs[0].spawn, None);
+    assert_eq!(sweep.matches().len(), 8);
+
+    assert!(matches!(
+        Sweep::from_toml(&format!(
+            "width = 4\nheight = 4\n{}[sweep]\nsample_every = 0",
+            RED
+        )),
+        Err(ScenarioError::Invalid(_))
+    ));
+}
+
+#[test]
+fn grid_params_change_only_what_they_set() {
+    let sweep = sweep(&format!(
+        r#"
+        {}
+        [params]
+        decay = 0.8
+        vision = 3
+        combat = {{ type = "lanchester", exponent = 2.0 }}
+        rebellion = {{ interval = 10, pocket_cells = 5 }}
+
+        [sweep]
+        params = [
+            {{ decay = 0.9 }},
+            {{ rebellion = {{ interval = 20 }} }},
+            {{ combat = {{ type = "dice", sides = 6 }} }},
+        ]
+        "#,
+        RED
+    ));
+    let configs = sweep.configs().unwrap();
+    let params = configs
+        .iter()
+        .map(|config| &config.params)
+        .collect::<Vec<_>>();
+    assert_eq!(params[0].decay, 0.9);
+    assert_eq!(params[0].vision, 3);
+    assert_eq!(params[0].combat, Combat::Lanchester { exponent: 2.0 });
+    assert_eq!(params[0].rebellion, sweep.scenario.params.rebellion);
+    assert_eq!(params[1].decay, 0.8);
+    assert_eq!(params[1].rebellion.interval, 20);
+    assert_eq!(params[1].rebellion.pocket_cells, 5);
+    assert_eq!(params[2].combat, Combat::Dice { sides: 6 });
+
+    // They're checked like the scenario's own.
+    for params in ["{ decay = 1.5 }", "{ flow = -0.5 }", "{ decya = 0.5 }"] {
+        let source = format!(
+            "width = 16\nheight = 16\n{}[sweep]\nparams = [{}]",
+            RED, params
+        );
+        assert!(Sweep::from_toml(&source).is_err(), "{}", params);
+    }
+}
+
+#[test]
+fn wins_and_draws_are_counted() {
+    // Red holds everything from the start with its own territory, but can't
+    // spread over the world from one cell in three ticks.
+    let report = sweep(&format!(
+        r#"
+        {}
+        [[victory]]
+        type = "territory"
+        share = 1.0
+
+        [sweep]
+        runs = 4
+        max_ticks = 3
+        sample_every = 1
+        spawns = [{{ strategy = "territories" }}, {{ strategy = "random" }}]
+        "#,
+        RED
+    ))
+    .run()
+    .unwrap();
+
+    let [territories, random] = &report.summaries[..] else {
+        panic!("expected two configs");
+    };
+    assert_eq!((territories.runs, territories.draws), (4, 0));
+    assert_eq!(territories.wins, [4]);
+    assert_eq!(territories.mean_ticks, Some(0.0));
+    assert_eq!((random.runs, random.draws), (4, 4));
+    assert_eq!(random.wins, [0]);
+    assert_eq!(random.mean_ticks, None);
+
+    assert_eq!(report.results.len(), 8);
+    for result in &report.results {
+        let won = result.setup.config == 0;
+        assert_eq!(result.winner.is_some(), won);
+        assert_eq!(result.ticks, if won { 0 } else { 3 });
+    }
+}
+
+#[test]
+fn territory_curves_are_averaged() {
+    // Red and Blue race over a small world, and some matches end sooner than
+    // others, between samples.
+    let report = sweep(&format!(
+        r#"
+        {}
+        [[empires]]
+        name = "Blue"
+        color = [0, 0, 255]
+
+        [[victory]]
+        type = "territory"
+        share = 0.6
+
+        [sweep]
+        runs = 8
+        max_ticks = 60
+        sample_every = 4
+        spawns = [{{ strategy = "random" }}]
+        "#,
+        RED
+    ))
+    .run()
+    .unwrap();
+
+    let results = &report.results;
+    for result in results {
+        let (last, rest) = result.territory.split_last().unwrap();
+        assert_eq!(last.0, result.ticks);
+        assert!(rest.iter().enumerate().all(|(i, (tick, _))| *tick == i * 4));
+    }
+    let ends = results.iter().map(|result| result.ticks);
+    assert!(ends.clone().min() < ends.clone().max());
+    assert!(results.iter().any(|result| result.ticks % 4 != 0));
+
+    let summary = &report.summaries[0];
+    let end = ends.max().unwrap();
+    let mut ticks = (0..end).step_by(4).collect::<Vec<_>>();
+    ticks.push(end);
+    assert_eq!(summary.ticks, ticks);
+    assert_eq!(summary.territory.len(), ticks.len());
+    for (&tick, mean) in ticks.iter().zip(&summary.territory) {
+        assert_eq!(mean.len(), 2);
+        for (empire, mean) in mean.iter().enumerate() {
+            // Matches that ended count with their final shares.
+            let expected = results
+                .iter()
+                .map(|result| {
+                    let sample = result.territory.iter().rev().find(|(t, _)| *t <= tick);
+                    sample.unwrap().1[empire]
+                })
+                .sum::<f32>()
+                / results.len() as f32;
+            assert!((mean - expected).abs() < 1e-5);
+        }
+    }
+
+    let mut csv = vec![];
+    report.write_curves(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("config,tick,Red,Blue"));
+    let labels = lines
+        .map(|line| line.split(',').nth(1).unwrap().parse().unwrap())
+        .collect::<Vec<usize>>();
+    assert_eq!(labels, ticks);
+}
+
+#[test]
+fn the_last_sample_is_labelled_with_its_tick() {
+    // Nobody can win, so every match runs to a `max_ticks` that isn't a
+    // multiple of `sample_every`.
+    let report = sweep(&format!(
+        r#"
+        {}
+        [[victory]]
+        type = "territory"
+        share = 1.0
+
+        [sweep]
+        runs = 2
+        max_ticks = 7
+        sample_every = 5
+        spawns = [{{ strategy = "random" }}]
+        "#,
+        RED
+    ))
+    .run()
+    .unwrap();
+
+    for result in &report.results {
+        let ticks = result.territory.iter().map(|(tick, _)| *tick);
+        assert_eq!(ticks.collect::<Vec<_>>(), [0, 5, 7]);
+    }
+    assert_eq!(report.summaries[0].ticks, [0, 5, 7]);
+
+    let mut csv = vec![];
+    report.write_curves(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let labels = csv
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').nth(1).unwrap());
+    assert_eq!(labels.collect::<Vec<_>>(), ["0", "5", "7"]);
+}
+
+#[test]
+fn rebels_win_for_their_empire() {
+    // Red covers the world and most of it breaks away on the first tick, so
+    // its rebels hold the most territory when time runs out.
+    let report = sweep(
+        r#"
+        [[empires]]
+        name = "Red"
+        color = [255, 0, 0]
+
+        [[victory]]
+        type = "tick_limit"
+        ticks = 1
+
+        [spawn]
+        strategy = "territories"
+
+        [params.rebellion]
+        interval = 1
+        max_cells = 10
+        breakaway = 0.6
+
+        [sweep]
+        runs = 3
+        sample_every = 1
+        "#,
+    )
+    .run()
+    .unwrap();
+
+    assert_eq!(report.empires, ["Red"]);
+    assert_eq!(report.summaries[0].wins, [3]);
+    for result in &report.results {
+        assert_eq!(result.winner, Some(1));
+        assert_eq!(result.territory.len(), 2);
+        assert_eq!(result.territory[1].0, 1);
+        assert!((result.territory[1].1[0] - 1.0).abs() < 1e-5);
+    }
+    assert!(report.to_string().contains("100.0% wins"));
+}
@@ -0,0 +1,179 @@
+use libterritory::{
+    scenario::Scenario,
+    tech::{Tech, Tier},
+    traits::Traits,
+    world::{Cell, Empire, Topology, World},
+};
+
+/// A row of four cells of empire 1 that stays as it is, researching toward
+/// two tiers.
+fn lab() -> World {
+    let mut world = World::new(4, 1);
+    world.seed = 2;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.tech.per_cell = 1;
+    world.params.tech.tiers = vec![
+        Tier {
+            research: 10,
+            traits: Traits {
+                attack: 2.0,
+                ..Default::default()
+            },
+        },
+        Tier {
+            research: 20,
+            traits: Traits {
+                attack: 1.5,
+                growth: 1.5,
+                ..Default::default()
+            },
+        },
+    ];
+    world.empires = vec![Empire::new(1, "Empire 1", (200, 50, 0, 255))];
+    world.cells.fill(Cell {
+        owner: 1,
+        troops: 1000,
+    });
+    world
+}
+
+#[test]
+fn territory_earns_research() {
+    let mut world = lab();
+    world.update();
+    world.update();
+    assert_eq!(
+        world.empires[0].tech,
+        Tech {
+            research: 8,
+            tier: 0
+        }
+    );
+    assert_eq!(world.empires[0].traits, Traits::default());
+
+    world.update();
+    assert_eq!(world.stats()[0].research, 12);
+    assert_eq!(world.stats()[0].tier, 1);
+    assert_eq!(world.empire_traits(&world.empires[0]).attack, 2.0);
+
+    world.update();
+    world.update();
+    assert_eq!(world.stats()[0].tier, 2);
+    let traits = world.empire_traits(&world.empires[0]);
+    assert_eq!(traits.attack, 3.0);
+    assert_eq!(traits.growth, 1.5);
+    // The tiers aren't baked into the empire's own traits.
+    assert_eq!(world.empires[0].traits, Traits::default());
+
+    // There is nothing left to unlock.
+    for _ in 0..10 {
+        world.update();
+    }
+    assert_eq!(world.empires[0].tech.tier, 2);
+}
+
+#[test]
+fn tiers_can_unlock_together() {
+    let mut world = lab();
+    world.params.tech.per_cell = 10;
+    world.update();
+    assert_eq!(world.empires[0].tech.tier, 2);
+
+    // Research tops out rather than overflowing.
+    let mut world = lab();
+    world.params.tech.per_cell = u64::MAX;
+    world.update();
+    world.update();
+    assert_eq!(
+        world.empires[0].tech,
+        Tech {
+            research: u64::MAX,
+            tier: 2
+        }
+    );
+
+    let mut world = lab();
+    world.params.tech.per_cell = 0;
+    for _ in 0..10 {
+        world.update();
+    }
+    assert_eq!(world.empires[0].tech, Tech::default());
+}
+
+#[test]
+fn rebels_keep_their_tiers() {
+    let mut world = lab();
+    world.params.tech.per_cell = 3;
+    world.params.rebellion.interval = 1;
+    world.params.rebellion.max_cells = 3;
+    world.params.rebellion.breakaway = 0.5;
+    world.update();
+
+    assert_eq!(world.empires.len(), 2);
+    assert_eq!(world.empires[0].tech.tier, 1);
+    assert_eq!(
+        world.empires[1].tech,
+        Tech {
+            research: 0,
+            tier: 1
+        }
+    );
+    assert_eq!(world.empires[1].traits, world.empires[0].traits);
+    assert_eq!(
+        world.empire_traits(&world.empires[1]),
+        world.empire_traits(&world.empires[0])
+    );
+}
+
+#[test]
+fn tiers_stack_onto_the_empires_own_traits() {
+    let mut world = lab();
+    world.empires[0].traits.attack = 0.5;
+    world.empires[0].tech.tier = 2;
+    assert_eq!(world.empire_traits(&world.empires[0]).attack, 1.5);
+
+    // Changing the tiers changes what they give the empires that have them.
+    world.params.tech.tiers[0].traits.attack = 4.0;
+    assert_eq!(world.empire_traits(&world.empires[0]).attack, 3.0);
+    world.params.tech.tiers.clear();
+    assert_eq!(world.empire_traits(&world.empires[0]).attack, 0.5);
+    assert_eq!(world.empires[0].traits.attack, 0.5);
+
+    // And empires play with them.
+    let mut base = lab();
+    base.params.tech.per_cell = 0;
+    base.set(0, 0, Cell::default());
+    let mut advanced = base.clone();
+    advanced.empires[0].tech.tier = 2;
+    base.update();
+    advanced.update();
+    assert!(advanced.get(0, 0).unwrap().troops > base.get(0, 0).unwrap().troops);
+}
+
+#[test]
+fn tech_is_set_in_scenarios() {
+    let scenario = Scenario::from_toml(
+        r#"
+        width = 4
+        height = 4
+
+        [params.tech]
+        per_cell = 2
+        tiers = [{ research = 100, traits = { defense = 1.5 } }]
+        "#,
+    )
+    .unwrap();
+    let tech = scenario.build().unwrap().params.tech;
+    assert_eq!(tech.per_cell, 2);
+    assert_eq!(
+        tech.tiers,
+        [Tier {
+            research: 100,
+            traits: Traits {
+                defense: 1.5,
+                ..Default::default()
+            }
+        }]
+    );
+}
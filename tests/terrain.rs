@@ -0,0 +1,61 @@
+use libterritory::terrain::{Terrain, TerrainGenerator, TerrainSource};
+
+fn generator(seed: u64) -> TerrainGenerator {
+    TerrainGenerator {
+        seed,
+        scale: 30.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn the_same_seed_makes_the_same_map() {
+    let map = generator(21).generate(120, 80);
+    assert_eq!(map.len(), 120 * 80);
+    assert_eq!(map, generator(21).generate(120, 80));
+    assert_ne!(map, generator(22).generate(120, 80));
+
+    // Every kind of terrain shows up, rivers included.
+    for terrain in [
+        Terrain::Plains,
+        Terrain::Water,
+        Terrain::Hills,
+        Terrain::Mountains,
+        Terrain::River,
+    ] {
+        assert!(map.contains(&terrain), "{:?}", terrain);
+    }
+}
+
+#[test]
+fn generators_handle_empty_and_tiny_worlds() {
+    assert!(generator(1).generate(0, 10).is_empty());
+    assert_eq!(generator(1).generate(1, 1).len(), 1);
+    let rivers = TerrainGenerator {
+        rivers: 0,
+        ..generator(1)
+    };
+    assert!(!rivers.generate(60, 40).contains(&Terrain::River));
+}
+
+#[test]
+fn ascii_maps_stretch_to_fit() {
+    let source = TerrainSource::Ascii {
+        rows: vec![".~".into(), "^=".into()],
+    };
+    let map = source.generate(4, 2).unwrap();
+    use Terrain::*;
+    assert_eq!(
+        map,
+        [Plains, Plains, Water, Water, Mountains, Mountains, River, River]
+    );
+
+    let bad = TerrainSource::Ascii {
+        rows: vec![".x".into()],
+    };
+    assert!(bad.generate(4, 4).unwrap_err().contains("'x'"));
+    let ragged = TerrainSource::Ascii {
+        rows: vec!["..".into(), ".".into()],
+    };
+    assert!(ragged.generate(4, 4).is_err());
+}
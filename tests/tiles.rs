@@ -0,0 +1,82 @@
+use libterritory::{
+    spawn::{Spawn, SpawnStrategy},
+    terrain::TerrainGenerator,
+    world::{Cell, Topology, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+mod common;
+
+fn world(width: usize, height: usize, topology: Topology, empires: u16) -> World {
+    let mut world = World::new(width, height);
+    world.seed = 7;
+    world.topology = topology;
+    world.terrain = TerrainGenerator {
+        seed: 7,
+        scale: 40.0,
+        ..Default::default()
+    }
+    .generate(width, height);
+    world.empires = common::empires(empires);
+    Spawn {
+        strategy: SpawnStrategy::EvenlySpaced,
+        fair: false,
+    }
+    .apply(&mut world, &mut StdRng::seed_from_u64(7));
+    world
+}
+
+fn assert_same_as_full(mut world: World, ticks: usize) {
+    let mut full = world.clone();
+    for tick in 0..ticks {
+        world.update();
+        full.update_full();
+        assert!(world.cells == full.cells, "diverged on tick {}", tick);
+    }
+}
+
+#[test]
+fn skipping_matches_full_update_on_torus() {
+    assert_same_as_full(world(200, 130, Topology::Torus, 4), 150);
+}
+
+#[test]
+fn skipping_matches_full_update_when_bounded() {
+    assert_same_as_full(world(130, 200, Topology::Bounded, 3), 150);
+}
+
+#[test]
+fn skipping_matches_full_update_on_worlds_smaller_than_a_tile() {
+    assert_same_as_full(world(20, 9, Topology::Torus, 2), 50);
+    assert_same_as_full(world(1, 40, Topology::Bounded, 1), 50);
+}
+
+#[test]
+fn skipping_notices_cells_changed_from_outside() {
+    let mut world = world(256, 256, Topology::Torus, 2);
+    let mut full = world.clone();
+    for _ in 0..30 {
+        world.update();
+        full.update_full();
+    }
+
+    let cell = Cell {
+        owner: 1,
+        troops: 60000,
+    };
+    world.set(250, 5, cell);
+    full.set(250, 5, cell);
+    world.cells.set(128 * 256 + 128, cell);
+    full.cells.set(128 * 256 + 128, cell);
+
+    assert_same_as_full(world, 60);
+}
+
+#[test]
+fn empty_world_stays_empty() {
+    let mut world = world(100, 100, Topology::Torus, 0);
+    for _ in 0..10 {
+        world.update();
+    }
+    assert!(world.cells.iter().all(|cell| cell == Cell::default()));
+}
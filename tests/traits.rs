@@ -0,0 +1,253 @@
+use libterritory::{
+    cells::Layout,
+    combat::Combat,
+    replay::Intervention,
+    scenario::Scenario,
+    spawn::{Spawn, SpawnStrategy},
+    terrain::{Terrain, TerrainGenerator},
+    traits::{Affinities, Faction, Traits},
+    world::{Cell, Empire, Topology, Troops, World},
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn empires(traits: &[Traits]) -> Vec<Empire> {
+    traits
+        .iter()
+        .enumerate()
+        .map(|(i, &traits)| {
+            let id = i as u16 + 1;
+            Empire {
+                traits,
+                ..Empire::new(id, format!("Empire {}", id), (255, 255, 255, 255))
+            }
+        })
+        .collect()
+}
+
+/// Two cells side by side, empire 1 with `attack` against empire 2 with
+/// `defend`, which is on `terrain`.
+fn duel(attack: Traits, defend: Traits, troops: [Troops; 2], terrain: Terrain) -> World {
+    let mut world = World::new(2, 1);
+    world.seed = 8;
+    world.topology = Topology::Bounded;
+    world.params.decay = 1.0;
+    world.params.takeover_min = 1.0;
+    world.params.takeover_max = 1.0;
+    world.empires = empires(&[attack, defend]);
+    world.terrain[1] = terrain;
+    world.set(
+        0,
+        0,
+        Cell {
+            owner: 1,
+            troops: troops[0],
+        },
+    );
+    world.set(
+        1,
+        0,
+        Cell {
+            owner: 2,
+            troops: troops[1],
+        },
+    );
+    world
+}
+
+fn owner_after(mut world: World) -> u16 {
+    world.update();
+    world.get(1, 0).unwrap().owner
+}
+
+#[test]
+fn attack_and_defense_tip_the_balance() {
+    let neutral = Traits::default();
+    let strong = Traits {
+        attack: 1.1,
+        ..neutral
+    };
+    let stubborn = Traits {
+        defense: 1.2,
+        ..neutral
+    };
+    assert_eq!(
+        owner_after(duel(neutral, neutral, [1000, 1000], Terrain::Plains)),
+        2
+    );
+    assert_eq!(
+        owner_after(duel(strong, neutral, [1000, 1000], Terrain::Plains)),
+        1
+    );
+    assert_eq!(
+        owner_after(duel(neutral, neutral, [1000, 900], Terrain::Plains)),
+        1
+    );
+    assert_eq!(
+        owner_after(duel(neutral, stubborn, [1000, 900], Terrain::Plains)),
+        2
+    );
+}
+
+#[test]
+fn affinities_only_count_on_their_terrain() {
+    let highlanders = Traits {
+        affinities: Affinities {
+            hills: 1.2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let neutral = Traits::default();
+    assert_eq!(
+        owner_after(duel(neutral, neutral, [1000, 600], Terrain::Hills)),
+        1
+    );
+    assert_eq!(
+        owner_after(duel(neutral, highlanders, [1000, 600], Terrain::Hills)),
+        2
+    );
+    assert_eq!(
+        owner_after(duel(neutral, highlanders, [1000, 900], Terrain::Plains)),
+        1
+    );
+}
+
+#[test]
+fn decay_and_growth_scale() {
+    let lone = |traits: Traits| {
+        let mut world = World::new(1, 1);
+        world.topology = Topology::Bounded;
+        world.params.decay = 0.5;
+        world.empires = empires(&[traits]);
+        world.set(
+            0,
+            0,
+            Cell {
+                owner: 1,
+                troops: 1000,
+            },
+        );
+        world.update();
+        world.get(0, 0).unwrap().troops
+    };
+    assert_eq!(lone(Traits::default()), 500);
+    assert_eq!(
+        lone(Traits {
+            decay: 0.5,
+            ..Default::default()
+        }),
+        750
+    );
+    assert_eq!(
+        lone(Traits {
+            decay: 2.0,
+            ..Default::default()
+        }),
+        0
+    );
+
+    let growing = Traits {
+        growth: 1.5,
+        ..Default::default()
+    };
+    let mut world = duel(growing, Traits::default(), [1000, 0], Terrain::Plains);
+    world.update();
+    assert_eq!(
+        world.get(1, 0),
+        Some(Cell {
+            owner: 1,
+            troops: 1500
+        })
+    );
+}
+
+#[test]
+fn factions_are_deterministic() {
+    for combat in [Combat::Takeover, Combat::Lanchester { exponent: 1.5 }] {
+        let mut world = World::new(80, 60);
+        world.seed = 9;
+        world.params.combat = combat;
+        world.terrain = TerrainGenerator {
+            seed: 9,
+            scale: 25.0,
+            ..Default::default()
+        }
+        .generate(80, 60);
+        let traits = Faction::ALL.map(Faction::traits);
+        world.empires = empires(&traits);
+        Spawn {
+            strategy: SpawnStrategy::Territories,
+            fair: false,
+        }
+        .apply(&mut world, &mut StdRng::seed_from_u64(9));
+        world.check_parallel = true;
+
+        let mut full = world.clone();
+        let mut soa = world.clone();
+        soa.set_layout(Layout::StructOfArrays);
+        for tick in 0..30 {
+            world.update();
+            full.update_full();
+            soa.update();
+            assert!(world.cells == full.cells, "{:?} tick {}", combat, tick);
+            assert!(
+                world.cells.iter().eq(soa.cells.iter()),
+                "{:?} tick {}",
+                combat,
+                tick
+            );
+        }
+        assert_eq!(world.parallel_mismatch, None);
+    }
+}
+
+#[test]
+fn added_empires_keep_their_traits() {
+    let mut world = World::new(4, 4);
+    world.apply(&Intervention::AddEmpire {
+        name: "Horde".into(),
+        color: (255, 0, 0, 255),
+        traits: Faction::Horde.traits(),
+    });
+    assert_eq!(world.empires[0].traits, Faction::Horde.traits());
+}
+
+#[test]
+fn traits_are_set_in_scenarios() {
+    let world = Scenario::from_toml(
+        r#"
+        width = 4
+        height = 4
+
+        [spawn]
+        strategy = "evenly_spaced"
+
+        [[empires]]
+        color = [255, 0, 0]
+        faction = "river_folk"
+
+        [[empires]]
+        color = [0, 0, 255]
+        traits = { attack = 1.5, affinities = { mountains = 2.0 } }
+
+        [[empires]]
+        color = [0, 255, 0]
+        "#,
+    )
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(world.empires[0].traits, Faction::RiverFolk.traits());
+    assert_eq!(
+        world.empires[1].traits,
+        Traits {
+            attack: 1.5,
+            affinities: Affinities {
+                mountains: 2.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    );
+    assert_eq!(world.empires[2].traits, Traits::default());
+}
@@ -0,0 +1,120 @@
+use libterritory::{
+    cells::Layout,
+    scenario::Scenario,
+    world::{Cell, Empire, Topology, Troops, World},
+};
+
+/// A bounded world with one cell of empire 1 surrounded by empire 2.
+fn world(center: Troops, around: Troops) -> World {
+    let mut world = World::new(3, 3);
+    world.seed = 1;
+    world.topology = Topology::Bounded;
+    world.empires = (1..=2)
+        .map(|id| Empire::new(id, format!("Empire {}", id), (200, 100, 50, 255)))
+        .collect();
+    for y in 0..3 {
+        for x in 0..3 {
+            let cell = if (x, y) == (1, 1) {
+                Cell {
+                    owner: 1,
+                    troops: center,
+                }
+            } else {
+                Cell {
+                    owner: 2,
+                    troops: around,
+                }
+            };
+            world.set(x, y, cell);
+        }
+    }
+    world
+}
+
+fn center(world: &World) -> Cell {
+    world.get(1, 1).unwrap()
+}
+
+#[test]
+fn takeovers_saturate_at_the_cap() {
+    for max in [Troops::MAX, 1000] {
+        for layout in Layout::ALL {
+            let mut world = world(0, max);
+            world.set_layout(layout);
+            world.params.max_troops = max;
+            world.params.decay = 1.0;
+            world.params.takeover_min = 1.5;
+            world.params.takeover_max = 2.0;
+            world.update();
+            assert_eq!(
+                center(&world),
+                Cell {
+                    owner: 2,
+                    troops: max
+                }
+            );
+            assert!(world.cells.iter().all(|cell| cell.troops <= max));
+        }
+    }
+}
+
+#[test]
+fn cells_above_the_cap_are_cut_down() {
+    let mut world = world(5000, 0);
+    world.params.max_troops = 1000;
+    world.params.decay = 1.0;
+    world.update();
+    assert_eq!(center(&world).troops, 1000);
+}
+
+#[test]
+fn decay_rounds_down_to_nothing() {
+    let mut world = world(1, 0);
+    world.update();
+    assert_eq!(center(&world).troops, 0);
+
+    let mut capped = self::world(20, 0);
+    capped.params.max_troops = 0;
+    capped.update();
+    assert!(capped.cells.iter().all(|cell| cell.troops == 0));
+}
+
+#[test]
+fn drawing_scales_with_the_cap() {
+    let mut world = world(0, 0);
+    world.params.max_troops = 1000;
+    let mut frame = vec![0; 9 * 4];
+
+    for (troops, red) in [(1000, 200), (500, 100), (5000, 200), (0, 0)] {
+        world.set(1, 1, Cell { owner: 1, troops });
+        world.draw(&mut frame);
+        assert_eq!(frame[4 * 4..4 * 4 + 4], [red, red / 2, red / 4, 255]);
+    }
+
+    world.params.max_troops = 0;
+    world.draw(&mut frame);
+}
+
+#[test]
+fn scenarios_start_within_the_cap() {
+    let scenario = |troops: u32| {
+        Scenario::from_toml(&format!(
+            "width = 8\nheight = 8\n[params]\nmax_troops = 100\n\
+             [[empires]]\ncolor = [255, 0, 0]\nstart = [1, 1]\ntroops = {}",
+            troops
+        ))
+        .unwrap()
+        .build()
+    };
+    assert!(scenario(100).is_ok());
+    assert!(scenario(101).is_err());
+}
+
+#[cfg(feature = "wide-troops")]
+#[test]
+fn wide_troops_go_past_u16() {
+    let mut world = world(1_000_000, 0);
+    world.update();
+    assert_eq!(center(&world).troops, 950_000);
+    assert_eq!(world.params.max_troops, u32::MAX);
+}
@@ -0,0 +1,57 @@
+import init, { World } from "./pkg/libterritory.js";
+
+const SIZE = 256;
+
+await init();
+
+const canvas = document.getElementById("world");
+const context = canvas.getContext("2d");
+const status = document.getElementById("status");
+canvas.width = SIZE;
+canvas.height = SIZE;
+
+const frame = new Uint8Array(SIZE * SIZE * 4);
+const image = new ImageData(new Uint8ClampedArray(frame.buffer), SIZE, SIZE);
+
+let world;
+let playing = true;
+
+function randomize() {
+  world?.free();
+  world = new World(SIZE, SIZE);
+  const empires = Number(document.getElementById("empires").value);
+  for (let i = 0; i < empires; i++) {
+    const byte = () => 64 + Math.floor(Math.random() * 192);
+    world.addEmpire(`Empire ${i + 1}`, byte(), byte(), byte());
+  }
+  world.spawn(true);
+}
+
+// Clicking drops a full cell of the first empire.
+canvas.addEventListener("click", (event) => {
+  const rect = canvas.getBoundingClientRect();
+  const x = Math.floor(((event.clientX - rect.left) / rect.width) * SIZE);
+  const y = Math.floor(((event.clientY - rect.top) / rect.height) * SIZE);
+  world.setCell(x, y, 1, 65535);
+});
+
+document.getElementById("play").addEventListener("click", (event) => {
+  playing = !playing;
+  event.target.textContent = playing ? "Pause" : "Play";
+});
+document.getElementById("randomize").addEventListener("click", randomize);
+
+function frameLoop() {
+  if (playing) {
+    world.update();
+  }
+  world.draw(frame);
+  context.putImageData(image, 0, 0);
+
+  const winner = world.winner();
+  status.textContent = `tick ${world.tick}` + (winner ? `, empire ${winner} won` : "");
+  requestAnimationFrame(frameLoop);
+}
+
+randomize();
+requestAnimationFrame(frameLoop);